@@ -29,11 +29,11 @@ fn fibonacci(bencher: divan::Bencher, n: i64) {
 	bencher.bench_local(|| {
 		let mut runtime = swear_lib::runtime::ContextStack::new(&ast);
 		while !runtime.is_finished() {
-			runtime.step();
+			runtime.step().unwrap();
 		}
 
 		let result = runtime.get_result().unwrap();
-		result.into_count().unwrap().count
+		result.into_count().unwrap().to_i64()
 	});
 }
 
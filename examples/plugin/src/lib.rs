@@ -0,0 +1,32 @@
+//! A native library for Swear scripts, loaded with `'swear_example_plugin'$>load<`.
+//!
+//! Built with `cargo build` in this directory.
+
+use std::collections::BTreeMap as HashMap;
+
+use swear_lib::dyn_libraries::swear_dyn_api;
+
+pub struct Example;
+
+#[swear_dyn_api]
+impl Example {
+	/// Twice the Count it's given.
+	fn double(count: i64) -> i64 {
+		count * 2
+	}
+
+	/// The Chars in upper case, followed by as many `!` as asked for.
+	fn shout(text: String, marks: Option<usize>) -> String {
+		text.to_uppercase() + &"!".repeat(marks.unwrap_or(1))
+	}
+
+	/// The sum of a Map's values, as long as they're all Counts.
+	fn total(counts: HashMap<String, f64>) -> f64 {
+		counts.values().sum()
+	}
+
+	/// Only fails the call, the script that made it carries on as usual.
+	fn panic() {
+		panic!("the example plugin was asked to panic");
+	}
+}
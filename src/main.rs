@@ -1,20 +1,260 @@
 #![feature(fn_traits)]
 
-use swear_lib::runtime::SwearRuntime;
-
-fn main() {
-	let parser = swear_lib::swear_parser::SwearParser::new();
-	let script = match parser.parse(include_str!("../testing_script.sw")) {
-		Ok(result) => result,
-		Err(e) => {
-			eprintln!("{e}");
-			return;
-		}
+use std::io::{Read, Write};
+use std::process::ExitCode;
+use std::sync::{Arc, Mutex};
+
+use swear_lib::context::IContext;
+use swear_lib::object::{from_arg, FunctionInfoBuilder, IntoSwear, Object};
+use swear_lib::runtime::{ContextStack, ObjectRef, SwearRuntime};
+use swear_lib::swear_parser::{Diagnostic, Expression, ParseError, SwearParser};
+
+const USAGE: &str = "\
+Usage:
+	swear run [FILE] [ARGS...]	Run a script, `-` or no FILE reads from stdin.
+	swear check [FILE]		Parse a script without running it.
+	swear repl			Evaluate lines interactively.
+	swear help			Show this message.
+
+Any ARGS are available to the script as the Deck `args`.
+Scripts can also call `>env NAME*<` to read an environment variable, and `>now<`
+for the seconds since the Unix epoch.
+Modules given to `import` are looked for next to the script, then in each
+directory listed in SWEAR_PATH. Native libraries given to `load` are looked
+for in the same places, then next to the swear executable.
+The exit code is taken from the script's result: a Count is used as is if it
+fits in 0..=255 and exits with 1 otherwise, a State exits with 0 when positive and 1 when negative, anything else exits with 0.";
+
+// Following the BSD sysexits convention.
+const EXIT_USAGE: u8 = 64;
+const EXIT_DATA: u8 = 65;
+const EXIT_NO_INPUT: u8 = 66;
+const EXIT_SOFTWARE: u8 = 70;
+
+fn main() -> ExitCode {
+	let mut args = std::env::args().skip(1);
+
+	match args.next().as_deref() {
+		Some("run") => {
+			let path = args.next();
+			run(path.as_deref(), args.collect())
+		},
+		Some("check") => {
+			let path = args.next();
+			if args.next().is_some() {
+				return usage_error("`check` takes a single file");
+			}
+			check(path.as_deref())
+		},
+		Some("repl") => {
+			if args.next().is_some() {
+				return usage_error("`repl` doesn't take any arguments");
+			}
+			repl()
+		},
+		Some("help" | "-h" | "--help") => {
+			println!("{USAGE}");
+			ExitCode::SUCCESS
+		},
+		Some(other) => usage_error(&format!("unknown command `{other}`")),
+		None => usage_error("no command given"),
+	}
+}
+
+fn usage_error(message: &str) -> ExitCode {
+	eprintln!("error: {message}\n\n{USAGE}");
+	ExitCode::from(EXIT_USAGE)
+}
+
+/// Reads a script from a path, or stdin if there's no path or it's `-`.
+/// Returns the source along with a name to report it under.
+fn read_source(path: Option<&str>) -> Result<(String, String), ExitCode> {
+	let result = match path {
+		None | Some("-") => {
+			let mut source = String::new();
+			std::io::stdin().read_to_string(&mut source).map(|_| (source, "<stdin>".to_string()))
+		},
+		Some(path) => std::fs::read_to_string(path).map(|source| (source, path.to_string())),
+	};
+
+	result.map_err(|e| {
+		eprintln!("error: failed to read {}: {e}", path.unwrap_or("<stdin>"));
+		ExitCode::from(EXIT_NO_INPUT)
+	})
+}
+
+fn parse(source: &str, origin: &str) -> Result<Expression, ExitCode> {
+	SwearParser::new().parse(source).map_err(|e| {
+		let diagnostic = Diagnostic::from_parse_error(source, &e);
+		eprint!("{}", diagnostic.report(source, origin));
+		ExitCode::from(EXIT_DATA)
+	})
+}
+
+fn check(path: Option<&str>) -> ExitCode {
+	let (source, origin) = match read_source(path) {
+		Ok(read) => read,
+		Err(code) => return code,
+	};
+
+	match parse(&source, &origin) {
+		Ok(_) => ExitCode::SUCCESS,
+		Err(code) => code,
+	}
+}
+
+fn run(path: Option<&str>, script_args: Vec<String>) -> ExitCode {
+	let (source, origin) = match read_source(path) {
+		Ok(read) => read,
+		Err(code) => return code,
+	};
+
+	let script = match parse(&source, &origin) {
+		Ok(script) => script,
+		Err(code) => return code,
+	};
+
+	let mut runtime = ContextStack::new(&script);
+	add_globals(&mut runtime);
+	add_module_paths(&mut runtime, path);
+	runtime.set("args".into(), script_args.into_swear().into());
+
+	if !finish(&mut runtime, &source, &origin) {
+		return ExitCode::from(EXIT_SOFTWARE);
+	}
+
+	match runtime.get_result() {
+		//? The OS only keeps a byte, so anything that doesn't fit would wrap around, possibly to 0.
+		Some(Object::Count(count)) => match u8::try_from(count.to_i64()) {
+			Ok(code) => ExitCode::from(code),
+			Err(_) => ExitCode::FAILURE,
+		},
+		Some(Object::State(state)) => match state.state {
+			true => ExitCode::SUCCESS,
+			false => ExitCode::FAILURE,
+		},
+		_ => ExitCode::SUCCESS,
+	}
+}
+
+/// The functions every script has, on top of the language's own.
+fn add_globals(runtime: &mut ContextStack) {
+	runtime
+		// Env function.
+		// Returns the environment variable with the given name, or Zip if it isn't set.
+		.add_function(FunctionInfoBuilder::new("env".to_string()).build_native(Arc::new(Mutex::new(|_, args: Vec<ObjectRef>, _| {
+			let name: String = from_arg(&args, 0)?;
+
+			Ok(Some(std::env::var(name).ok().into_swear()))
+		}))))
+		// Now function.
+		// Returns the seconds since the Unix epoch.
+		.add_function(FunctionInfoBuilder::new("now".to_string()).build_native(Arc::new(Mutex::new(|_, _, _| {
+			let now = std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH).map_err(|_| ())?;
+
+			Ok(Some(now.as_secs_f64().into_swear()))
+		}))));
+}
+
+/// Points `import` and `load` at the directory the script is in, then SWEAR_PATH.
+/// Scripts without a file look in the working directory instead.
+fn add_module_paths(runtime: &mut ContextStack, path: Option<&str>) {
+	let dir = match path {
+		None | Some("-") => std::path::PathBuf::from("."),
+		Some(path) => std::path::Path::new(path).parent().map(|dir| dir.to_path_buf()).unwrap_or_default(),
 	};
+	//? A bare file name has an empty parent, which wouldn't find anything.
+	let dir = if dir.as_os_str().is_empty() { ".".into() } else { dir };
 
-	let mut runtime = swear_lib::runtime::ContextStack::new(&script);
+	let paths = std::env::var_os("SWEAR_PATH");
+	let paths = paths.iter().flat_map(std::env::split_paths);
+	for dir in std::iter::once(dir).chain(paths) {
+		runtime.add_module_path(&dir);
+		runtime.add_library_path(dir);
+	}
+}
+
+/// Steps the runtime until it's done, reporting any error against `source`.
+/// Returns false if the script failed.
+fn finish(runtime: &mut ContextStack, source: &str, origin: &str) -> bool {
 	while !runtime.is_finished() {
-		runtime.step();
+		if let Err(e) = runtime.step() {
+			match e.span().or_else(|| runtime.current_span()) {
+				Some(span) => eprint!("{}", Diagnostic::new(e.to_string(), span).report(source, origin)),
+				None => eprintln!("error: {e}"),
+			}
+
+			//? Errors in imported modules are wrapped once for each module they happened in.
+			let mut cause = std::error::Error::source(&e);
+			while let Some(e) = cause {
+				eprintln!("caused by: {e}");
+				cause = e.source();
+			}
+			return false;
+		}
 	}
-	std::hint::black_box(runtime.get_result().unwrap());
+
+	true
+}
+
+fn repl() -> ExitCode {
+	//? The runtime borrows every script it runs for as long as it lives,
+	//? so each input is leaked. A session doesn't last long enough for that to matter.
+	let mut runtime = ContextStack::new(Box::leak(Box::default()));
+	add_globals(&mut runtime);
+	add_module_paths(&mut runtime, None);
+	let parser = SwearParser::new();
+	let stdin = std::io::stdin();
+	let mut input = String::new();
+	//? Every input that ran, so errors in callbacks from earlier inputs can point at where they were written.
+	//? Inputs are parsed behind as many spaces as came before them, which makes their spans point into this.
+	let mut session = String::new();
+
+	loop {
+		print!("{}", if input.is_empty() { "swear> " } else { "  ...> " });
+		let _ = std::io::stdout().flush();
+
+		let mut line = String::new();
+		match stdin.read_line(&mut line) {
+			Ok(0) => break,
+			Ok(_) => {},
+			Err(e) => {
+				eprintln!("error: failed to read input: {e}");
+				return ExitCode::from(EXIT_NO_INPUT);
+			},
+		}
+
+		let blank = line.trim().is_empty();
+		input.push_str(&line);
+		if input.trim().is_empty() {
+			input.clear();
+			continue;
+		}
+
+		let padded = " ".repeat(session.len()) + &input;
+		let script = match parser.parse(&padded) {
+			Ok(script) => script,
+			// Keep reading until the input is complete, or a blank line gives up on it.
+			Err(ParseError::UnrecognizedEof { .. }) if !blank => continue,
+			Err(e) => {
+				let source = session.clone() + &input;
+				eprint!("{}", Diagnostic::from_parse_error(&source, &e).report(&source, "<repl>"));
+				input.clear();
+				continue;
+			},
+		};
+
+		session.push_str(&input);
+		runtime.append(Box::leak(Box::new(script)));
+		if finish(&mut runtime, &session, "<repl>")
+			&& let Some(result) = runtime.take_result()
+			&& !result.is_zip() {
+			println!("{result:?}");
+		}
+
+		input.clear();
+	}
+
+	println!();
+	ExitCode::SUCCESS
 }
@@ -1,5 +1,7 @@
+use std::path::PathBuf;
+
 use crate::runtime::operations::Operations;
-use std::collections::BTreeMap as HashMap;
+use swear_parser::Span;
 
 use super::*;
 use enum_dispatch::enum_dispatch;
@@ -21,6 +23,11 @@ impl<'rt> From<BlueprintContext<'rt>> for ContextHolder<'rt> {
 		ContextHolder::RuntimeContext(RuntimeContext::Blueprint(context))
 	}
 }
+impl<'rt> From<ModuleContext<'rt>> for ContextHolder<'rt> {
+	fn from(context: ModuleContext<'rt>) -> Self {
+		ContextHolder::RuntimeContext(RuntimeContext::Module(context))
+	}
+}
 
 #[derive(Clone, Debug)]
 #[enum_dispatch(IContext)]
@@ -28,6 +35,7 @@ impl<'rt> From<BlueprintContext<'rt>> for ContextHolder<'rt> {
 pub enum RuntimeContext<'rt> {
 	ContextLevel(ContextLevel<'rt>),
 	Blueprint(BlueprintContext<'rt>),
+	Module(ModuleContext<'rt>),
 }
 
 impl<'rt> IntoIterator for RuntimeContext<'rt> {
@@ -37,6 +45,7 @@ impl<'rt> IntoIterator for RuntimeContext<'rt> {
 		match self {
 			RuntimeContext::ContextLevel(context) => context.into_iter(),
 			RuntimeContext::Blueprint(context) => context.into_iter(),
+			RuntimeContext::Module(context) => context.into_iter(),
 		}
 	}
 }
@@ -65,42 +74,56 @@ pub trait IRuntimeContext<'rt> {
 	fn instructions(&self) -> &'rt Expression;
 	fn instr_index(&self) -> usize;
 	fn instr_index_mut(&mut self) -> &mut usize;
+	fn scope(&self) -> &Scope<'rt>;
 }
 
 #[derive(Clone, Debug)]
 pub struct ContextLevel<'rt> {
-	pub items: HashMap<String, ContextItem<'rt>>,
+	pub scope: Scope<'rt>,
 	pub instructions: &'rt Expression,
 	pub instr_index: usize,
 	pub ops: Vec<Operations<'rt>>,
+	/// Whether this level is the body of a callback.
+	/// Loop droppers can't reach past it to a loop in the caller.
+	pub call: bool,
 }
 
 impl<'rt> IntoIterator for ContextLevel<'rt> {
 	type Item = (String, ContextItem<'rt>);
 	type IntoIter = std::collections::btree_map::IntoIter<String, ContextItem<'rt>>;
 	fn into_iter(self) -> Self::IntoIter {
-		self.items.into_iter()
+		//? Iterating has no way to fail, so a poisoned scope has nothing to give.
+		self.scope.items().unwrap_or_default().into_iter()
 	}
 }
 
 impl<'rt> ContextLevel<'rt> {
-	pub fn new(instructions: &'rt Expression) -> Self {
+	pub fn new(instructions: &'rt Expression, scope: Scope<'rt>) -> Self {
 		Self {
-			items: HashMap::default(),
+			scope,
 			instructions,
 			instr_index: 0,
 			ops: Vec::new(),
+			call: false,
+		}
+	}
+
+	/// A level for running the body of a callback.
+	pub fn new_call(instructions: &'rt Expression, scope: Scope<'rt>) -> Self {
+		Self {
+			call: true,
+			..Self::new(instructions, scope)
 		}
 	}
 }
 
 impl<'rt> IContext<'rt> for ContextLevel<'rt> {
 	fn get(&self, key: &str) -> Option<ContextItem<'rt>> {
-		self.items.get(key).cloned()
+		self.scope.get(key)
 	}
 
 	fn set(&mut self, key: String, value: ContextItem<'rt>) {
-		self.items.insert(key, value);
+		self.scope.set(key, value);
 	}
 }
 
@@ -120,42 +143,50 @@ impl<'rt> IRuntimeContext<'rt> for ContextLevel<'rt> {
 	fn instr_index_mut(&mut self) -> &mut usize {
 		&mut self.instr_index
 	}
+	fn scope(&self) -> &Scope<'rt> {
+		&self.scope
+	}
 }
 
 #[derive(Clone, Debug)]
 pub struct BlueprintContext<'rt> {
-	pub items: HashMap<String, ContextItem<'rt>>,
+	pub scope: Scope<'rt>,
 	pub instructions: &'rt Expression,
 	pub instr_index: usize,
 	pub ops: Vec<Operations<'rt>>,
+	/// The height of the table when the blueprint started.
+	/// Anything its body leaves above it is dropped once it's built.
+	pub table: usize,
 }
 
 impl<'rt> IntoIterator for BlueprintContext<'rt> {
 	type Item = (String, ContextItem<'rt>);
 	type IntoIter = std::collections::btree_map::IntoIter<String, ContextItem<'rt>>;
 	fn into_iter(self) -> Self::IntoIter {
-		self.items.into_iter()
+		//? Iterating has no way to fail, so a poisoned scope has nothing to give.
+		self.scope.items().unwrap_or_default().into_iter()
 	}
 }
 
 impl<'rt> BlueprintContext<'rt> {
-	pub fn new(instructions: &'rt Expression) -> Self {
+	pub fn new(instructions: &'rt Expression, scope: Scope<'rt>, table: usize) -> Self {
 		Self {
-			items: HashMap::default(),
+			scope,
 			instructions,
 			instr_index: 0,
 			ops: Vec::new(),
+			table,
 		}
 	}
 }
 
 impl<'rt> IContext<'rt> for BlueprintContext<'rt> {
 	fn get(&self, key: &str) -> Option<ContextItem<'rt>> {
-		self.items.get(key).cloned()
+		self.scope.get(key)
 	}
 
 	fn set(&mut self, key: String, value: ContextItem<'rt>) {
-		self.items.insert(key, value);
+		self.scope.set(key, value);
 	}
 }
 
@@ -175,4 +206,67 @@ impl<'rt> IRuntimeContext<'rt> for BlueprintContext<'rt> {
 	fn instr_index_mut(&mut self) -> &mut usize {
 		&mut self.instr_index
 	}
+	fn scope(&self) -> &Scope<'rt> {
+		&self.scope
+	}
+}
+
+/// An imported module being run.
+/// Once it's done, its top level definitions become a Dynamic for the import to return.
+#[derive(Clone, Debug)]
+pub struct ModuleContext<'rt> {
+	pub scope: Scope<'rt>,
+	pub instructions: &'rt Expression,
+	pub instr_index: usize,
+	pub ops: Vec<Operations<'rt>>,
+	/// The height of the table when the module started.
+	/// Anything its body leaves above it is dropped once it's done.
+	pub table: usize,
+	/// The name it was imported as.
+	pub name: String,
+	/// The file it was read from, along with its text, to point at errors in it.
+	pub path: PathBuf,
+	pub source: &'rt str,
+	/// Where it was imported.
+	pub span: Span,
+}
+
+impl<'rt> IntoIterator for ModuleContext<'rt> {
+	type Item = (String, ContextItem<'rt>);
+	type IntoIter = std::collections::btree_map::IntoIter<String, ContextItem<'rt>>;
+	fn into_iter(self) -> Self::IntoIter {
+		//? Iterating has no way to fail, so a poisoned scope has nothing to give.
+		self.scope.items().unwrap_or_default().into_iter()
+	}
+}
+
+impl<'rt> IContext<'rt> for ModuleContext<'rt> {
+	fn get(&self, key: &str) -> Option<ContextItem<'rt>> {
+		self.scope.get(key)
+	}
+
+	fn set(&mut self, key: String, value: ContextItem<'rt>) {
+		self.scope.set(key, value);
+	}
+}
+
+impl<'rt> IRuntimeContext<'rt> for ModuleContext<'rt> {
+	fn ops(&self) -> &Vec<Operations<'rt>> {
+		&self.ops
+	}
+	fn ops_mut(&mut self) -> &mut Vec<Operations<'rt>> {
+		&mut self.ops
+	}
+	fn instructions(&self) -> &'rt Expression {
+		self.instructions
+	}
+	fn instr_index(&self) -> usize {
+		self.instr_index
+	}
+	fn instr_index_mut(&mut self) -> &mut usize {
+		&mut self.instr_index
+	}
+	fn scope(&self) -> &Scope<'rt> {
+		&self.scope
+	}
 }
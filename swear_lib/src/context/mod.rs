@@ -1,8 +1,10 @@
 mod context;
 mod object_ref;
+mod scope;
 
 pub use context::*;
 pub use object_ref::*;
+pub use scope::*;
 use swear_parser::Expression;
 
 use crate::object::*;
@@ -53,6 +55,8 @@ impl<'rt> From<NativeCallback<'rt>> for Callback<'rt> {
 pub struct SwearCallback<'rt> {
 	pub args: Vec<String>,
 	pub callback: &'rt Expression,
+	/// Where the callback was defined, names in its body are looked up from here.
+	pub scope: Scope<'rt>,
 }
 
 #[repr(C)] //? Used in Dynamic libraries.
@@ -70,7 +74,12 @@ impl<'rt> Debug for NativeCallback<'rt> {
 
 #[derive(Debug, Clone)]
 pub struct Blueprint<'rt> {
+	pub args: Vec<String>,
+	/// The names of the blueprints this one extends.
+	pub parents: Vec<String>,
 	pub expr: &'rt Expression,
+	/// Where the blueprint was defined, names in its body are looked up from here.
+	pub scope: Scope<'rt>,
 }
 
 // impl From<Expression> for Blueprint {
@@ -1,6 +1,7 @@
 use std::hash::Hash;
 
 use super::*;
+use crate::runtime::SwearError;
 
 #[repr(C)] //? Used in Dynamic libraries.
 #[derive(Clone, Default)]
@@ -24,15 +25,32 @@ impl<'rt> IntoIterator for ObjectRef<'rt> {
 
 impl<'rt> PartialEq for ObjectRef<'rt> {
 	fn eq(&self, other: &Self) -> bool {
-		*self.access() == *other.access()
+		Arc::ptr_eq(&self.inner, &other.inner) || *self.access() == *other.access()
 	}
 }
 
 impl<'rt> Eq for ObjectRef<'rt> {}
 
+impl<'rt> PartialOrd for ObjectRef<'rt> {
+	fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+		Some(self.cmp(other))
+	}
+}
+
+impl<'rt> Ord for ObjectRef<'rt> {
+	fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+		if Arc::ptr_eq(&self.inner, &other.inner) {
+			return std::cmp::Ordering::Equal;
+		}
+
+		self.access().cmp(&other.access())
+	}
+}
+
+//? Hashes the value rather than the pointer, to agree with PartialEq.
 impl<'rt> Hash for ObjectRef<'rt> {
 	fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
-		Arc::as_ptr(&self.inner).hash(state)
+		self.access().hash(state)
 	}
 }
 
@@ -59,6 +77,27 @@ impl<'rt> ObjectRef<'rt> {
 		}
 	}
 
+	/// Like [`ObjectRef::access`], but reports a poisoned lock instead of panicking.
+	pub fn try_access(&self) -> Result<std::sync::RwLockReadGuard<'_, Object<'rt>>, SwearError> {
+		Ok(self.inner.read()?)
+	}
+
+	/// Like [`ObjectRef::lock`], but reports a poisoned lock instead of panicking.
+	pub fn try_lock(&self) -> Result<std::sync::RwLockWriteGuard<'_, Object<'rt>>, SwearError> {
+		Ok(self.inner.write()?)
+	}
+
+	/// A new reference to a shallow copy of the referenced Object.
+	/// Changes to the copy don't affect the original.
+	pub fn snapshot(&self) -> Self {
+		Self::new(self.access().clone())
+	}
+
+	/// Whether both refer to the very same Object.
+	pub fn ptr_eq(&self, other: &Self) -> bool {
+		Arc::ptr_eq(&self.inner, &other.inner)
+	}
+
 	/// Identical to cloning, but doesn't look like cloning.
 	#[inline(always)]
 	pub fn copy(&self) -> Self {
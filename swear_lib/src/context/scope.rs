@@ -0,0 +1,230 @@
+use std::collections::BTreeMap as HashMap;
+
+use super::*;
+use crate::runtime::SwearError;
+
+/// The names defined in one level of a script, along with the levels it's nested in.
+///
+/// Scopes are shared, so callbacks can hold on to the one they were defined in.
+/// Names are looked up through the scope a callback was defined in, not the one it was called from,
+/// and they still see changes made to it after it was left.
+#[derive(Clone, Default)]
+pub struct Scope<'rt>(Arc<Frame<'rt>>);
+
+#[derive(Default)]
+struct Frame<'rt> {
+	items: RwLock<HashMap<String, Slot<'rt>>>,
+	/// The Object a method was called on.
+	/// Its contents are visible from this frame, and definitions of names it already has go into it.
+	target: Option<ObjectRef<'rt>>,
+	parent: Option<Scope<'rt>>,
+}
+
+/// How a frame holds one of its names.
+//? A callback or blueprint defined in a frame would keep that frame alive from inside it,
+//? so those are held without their scope, and get it back whenever they're looked up.
+//? Anything else that reaches the frame, like an Object holding one of its callbacks, still counts.
+enum Slot<'rt> {
+	Item(ContextItem<'rt>),
+	Callback {
+		args: Vec<String>,
+		callback: &'rt Expression,
+	},
+	Blueprint {
+		args: Vec<String>,
+		parents: Vec<String>,
+		expr: &'rt Expression,
+	},
+}
+
+impl<'rt> Slot<'rt> {
+	fn hold(item: ContextItem<'rt>, frame: &Scope<'rt>) -> Self {
+		match item {
+			ContextItem::Callback(Callback::Swear(SwearCallback { args, callback, scope })) if scope.ptr_eq(frame) => {
+				Slot::Callback { args, callback }
+			},
+			ContextItem::Blueprint(Blueprint { args, parents, expr, scope }) if scope.ptr_eq(frame) => {
+				Slot::Blueprint { args, parents, expr }
+			},
+			item => Slot::Item(item),
+		}
+	}
+
+	fn item(&self, frame: &Scope<'rt>) -> ContextItem<'rt> {
+		match self {
+			Slot::Item(item) => item.clone(),
+			Slot::Callback { args, callback } => ContextItem::Callback(Callback::Swear(SwearCallback {
+				args: args.clone(),
+				callback,
+				scope: frame.clone(),
+			})),
+			Slot::Blueprint { args, parents, expr } => ContextItem::Blueprint(Blueprint {
+				args: args.clone(),
+				parents: parents.clone(),
+				expr,
+				scope: frame.clone(),
+			}),
+		}
+	}
+}
+
+impl<'rt> Scope<'rt> {
+	/// A scope nested in this one.
+	pub fn child(&self) -> Self {
+		self.child_of(None)
+	}
+
+	/// A scope nested in this one, for the body of a method called on `target`.
+	pub fn child_of(&self, target: Option<ObjectRef<'rt>>) -> Self {
+		Self(Arc::new(Frame {
+			items: RwLock::default(),
+			target,
+			parent: Some(self.clone()),
+		}))
+	}
+
+	pub fn parent(&self) -> Option<&Scope<'rt>> {
+		self.0.parent.as_ref()
+	}
+
+	/// Whether both are the same scope, rather than two with the same contents.
+	pub fn ptr_eq(&self, other: &Self) -> bool {
+		Arc::ptr_eq(&self.0, &other.0)
+	}
+
+	/// Where this scope lives, the same for every copy of it.
+	pub(crate) fn addr(&self) -> usize {
+		Arc::as_ptr(&self.0) as usize
+	}
+
+	/// Defines a name in this scope itself, even if it belongs to a method's target.
+	pub fn bind(&self, key: String, value: ContextItem<'rt>) -> Result<(), SwearError> {
+		let slot = Slot::hold(value, self);
+		self.0.items.write()?.insert(key, slot);
+		Ok(())
+	}
+
+	/// Defines a name the way a script does.
+	/// Inside a method, however deeply nested, a name its instance already has is set on the instance.
+	/// Anything else is defined in this scope, so new fields can only be added with `set`.
+	pub fn define(&self, key: String, value: ContextItem<'rt>) -> Result<(), SwearError> {
+		if let Some(target) = self.target() {
+			let is_field = target.try_access()?.as_dynamic().is_some_and(|obj| obj.get(&key).is_some());
+			if is_field {
+				target.clone().set(key, value);
+				return Ok(());
+			}
+		}
+
+		self.bind(key, value)
+	}
+
+	/// The Object the innermost method this scope is part of was called on.
+	fn target(&self) -> Option<&ObjectRef<'rt>> {
+		let mut scope = Some(self);
+		while let Some(current) = scope {
+			if let Some(target) = &current.0.target {
+				return Some(target);
+			}
+			scope = current.parent();
+		}
+
+		None
+	}
+
+	/// The names defined in this scope itself, without those of its target or parents.
+	pub fn items(&self) -> Result<HashMap<String, ContextItem<'rt>>, SwearError> {
+		Ok(self.0.items.read()?.iter().map(|(key, slot)| (key.clone(), slot.item(self))).collect())
+	}
+
+	/// Looks a name up in this scope alone, then its target.
+	fn get_local(&self, key: &str) -> Option<ContextItem<'rt>> {
+		//? Lookups can't report a poisoned lock, but a panic can't leave the map half written either.
+		let items = self.0.items.read().unwrap_or_else(std::sync::PoisonError::into_inner);
+		if let Some(slot) = items.get(key) {
+			return Some(slot.item(self));
+		}
+		drop(items);
+
+		match &self.0.target {
+			Some(target) if target.access().is_dynamic() => target.get(key),
+			_ => None,
+		}
+	}
+}
+
+impl<'rt> IContext<'rt> for Scope<'rt> {
+	fn get(&self, key: &str) -> Option<ContextItem<'rt>> {
+		let mut scope = Some(self);
+		while let Some(current) = scope {
+			if let Some(item) = current.get_local(key) {
+				return Some(item);
+			}
+			scope = current.parent();
+		}
+
+		None
+	}
+
+	//? There's no way to report a poisoned lock from here, the runtime goes through `define` instead.
+	fn set(&mut self, key: String, value: ContextItem<'rt>) {
+		let _ = self.define(key, value);
+	}
+}
+
+//? Only the names are written, a callback in the scope would lead straight back to it.
+impl<'rt> Debug for Scope<'rt> {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		match self.0.items.try_read() {
+			Ok(items) => write!(f, "Scope({:?})", items.keys().collect::<Vec<_>>()),
+			Err(_) => write!(f, "Scope(LOCKED)"),
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn callback<'rt>(body: &'rt Expression, scope: &Scope<'rt>) -> ContextItem<'rt> {
+		ContextItem::Callback(Callback::Swear(SwearCallback {
+			args: Vec::new(),
+			callback: body,
+			scope: scope.clone(),
+		}))
+	}
+
+	/// Test that a callback only keeps the scope it was defined in alive once it's taken out of it.
+	#[test]
+	fn test_frame_freed() {
+		let body = Expression::default();
+		let scope = Scope::default();
+		scope.bind("f".into(), callback(&body, &scope)).expect("Failed to bind");
+
+		let frame = Arc::downgrade(&scope.0);
+		let escaped = scope.get("f");
+		drop(scope);
+		assert!(frame.upgrade().is_some(), "A callback taken out of its scope should keep it alive");
+
+		drop(escaped);
+		assert!(frame.upgrade().is_none(), "A scope should be freed along with its own callbacks");
+	}
+
+	/// Test that a callback looked up from its scope still refers to it.
+	#[test]
+	fn test_callback_scope() {
+		let body = Expression::default();
+		let scope = Scope::default();
+		let child = scope.child();
+		scope.bind("f".into(), callback(&body, &scope)).expect("Failed to bind");
+		child.bind("g".into(), callback(&body, &scope)).expect("Failed to bind");
+
+		for name in ["f", "g"] {
+			let Some(ContextItem::Callback(Callback::Swear(found))) = child.get(name) else {
+				panic!("Expected a callback named {name}");
+			};
+			assert!(found.scope.ptr_eq(&scope));
+		}
+		assert_eq!(scope.items().expect("Failed to read").len(), 1);
+	}
+}
@@ -0,0 +1,134 @@
+use std::ffi::c_void;
+
+/// Bumped whenever anything in this module changes in a way old plugins can't cope with.
+pub const ABI_VERSION: u32 = 1;
+
+/// `extern "C" fn() -> u32`, returning the [`ABI_VERSION`] the plugin was built against.
+pub const ABI_VERSION_SYMBOL: &[u8] = b"swear_plugin_abi_version\0";
+/// [`SwearPluginInit`], called once the version is known to match.
+pub const INIT_SYMBOL: &[u8] = b"swear_plugin_init\0";
+
+/// A handle to an Object.
+///
+/// Handles given to a plugin function are only borrowed for the length of the call.
+/// Handles made by the host's `new_*`, `get` and `share` functions are owned by the plugin,
+/// and must either be returned from a plugin function or given back with `release`.
+/// They can be kept for as long as the plugin likes, so the host only gives out owned handles
+/// to Objects it doesn't need to keep alive itself: never to a Dynamic or a Callable, or to a
+/// Deck or Map with one inside. Those functions return null for them instead.
+#[repr(C)]
+pub struct SwearValue {
+	_opaque: [u8; 0],
+}
+
+/// A handle to the Object a plugin is adding its functions to.
+#[repr(C)]
+pub struct SwearPlugin {
+	_opaque: [u8; 0],
+}
+
+/// UTF-8 text, borrowed for the length of a call. Not null terminated.
+#[repr(C)]
+#[derive(Clone, Copy)]
+pub struct SwearStr {
+	pub ptr: *const u8,
+	pub len: usize,
+}
+
+impl SwearStr {
+	pub fn new(text: &str) -> Self {
+		Self { ptr: text.as_ptr(), len: text.len() }
+	}
+
+	/// # Safety
+	/// `ptr` must point to `len` bytes that stay valid for `'a`.
+	/// Invalid UTF-8 is replaced rather than trusted.
+	pub unsafe fn to_str<'a>(self) -> std::borrow::Cow<'a, str> {
+		if self.ptr.is_null() {
+			return std::borrow::Cow::Borrowed("");
+		}
+		String::from_utf8_lossy(unsafe { std::slice::from_raw_parts(self.ptr, self.len) })
+	}
+}
+
+/// What kind of Object a handle refers to.
+#[repr(u32)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SwearKind {
+	Zip = 0,
+	State = 1,
+	Count = 2,
+	Chars = 3,
+	Deck = 4,
+	Map = 5,
+	Dynamic = 6,
+	Callable = 7,
+}
+
+/// A function a plugin adds to its Object.
+///
+/// Gets back the `data` it was registered with, the Object it was called on and its arguments.
+/// Returns an owned handle to its result, or null if it failed.
+pub type SwearPluginFn = unsafe extern "C" fn(
+	data: *mut c_void,
+	host: *const SwearHost,
+	target: *const SwearValue,
+	args: *const *const SwearValue,
+	arg_count: usize,
+) -> *mut SwearValue;
+
+/// Called when a function's `data` is no longer needed.
+pub type SwearDropFn = unsafe extern "C" fn(data: *mut c_void);
+
+/// The plugin's entry point. Registers its functions on `plugin`, returns false if it failed.
+pub type SwearPluginInit = unsafe extern "C" fn(host: *const SwearHost, plugin: *mut SwearPlugin) -> bool;
+
+/// Everything the host lets a plugin do.
+///
+/// New functions are only ever added to the end, and `size` tells how many there are,
+/// so a plugin can check for one before calling it.
+///
+/// None of them unwind into the plugin. If one fails unexpectedly, it returns null, false or 0.
+#[repr(C)]
+pub struct SwearHost {
+	pub abi_version: u32,
+	/// The size of this table in bytes.
+	pub size: usize,
+
+	/// Adds a function to the plugin's Object. `drop_data` may be null.
+	pub register: unsafe extern "C" fn(plugin: *mut SwearPlugin, name: SwearStr, function: SwearPluginFn, data: *mut c_void, drop_data: Option<SwearDropFn>),
+
+	pub kind: unsafe extern "C" fn(value: *const SwearValue) -> SwearKind,
+	pub to_state: unsafe extern "C" fn(value: *const SwearValue) -> bool,
+	pub to_count: unsafe extern "C" fn(value: *const SwearValue) -> f64,
+	/// Like `to_count`, but exact for whole numbers that fit.
+	pub to_int: unsafe extern "C" fn(value: *const SwearValue) -> i64,
+	/// Copies as much of the value's text as fits into `buffer`.
+	/// Returns the full length in bytes, which may be more than `capacity`.
+	pub read_chars: unsafe extern "C" fn(value: *const SwearValue, buffer: *mut u8, capacity: usize) -> usize,
+	/// The number of items in the value, as a Deck.
+	pub len: unsafe extern "C" fn(value: *const SwearValue) -> usize,
+	/// An item of a Deck or a Map's value for a key, or null if there isn't one or it can't be owned.
+	pub get: unsafe extern "C" fn(value: *const SwearValue, key: *const SwearValue) -> *mut SwearValue,
+
+	pub new_zip: unsafe extern "C" fn() -> *mut SwearValue,
+	pub new_state: unsafe extern "C" fn(state: bool) -> *mut SwearValue,
+	pub new_count: unsafe extern "C" fn(count: f64) -> *mut SwearValue,
+	pub new_int: unsafe extern "C" fn(count: i64) -> *mut SwearValue,
+	pub new_chars: unsafe extern "C" fn(chars: SwearStr) -> *mut SwearValue,
+	/// A Deck of the given items. The items are borrowed, not taken.
+	/// Null if any of them can't be owned.
+	pub new_deck: unsafe extern "C" fn(items: *const *const SwearValue, len: usize) -> *mut SwearValue,
+	/// A Map pairing each key with the value at the same position. Both are borrowed, not taken.
+	/// Null if any of them can't be owned.
+	pub new_map: unsafe extern "C" fn(keys: *const *const SwearValue, values: *const *const SwearValue, len: usize) -> *mut SwearValue,
+
+	/// Another owned handle to the very same Object, or null if it can't be owned.
+	pub share: unsafe extern "C" fn(value: *const SwearValue) -> *mut SwearValue,
+	/// Gives back an owned handle.
+	pub release: unsafe extern "C" fn(value: *mut SwearValue),
+
+	/// The value as a Deck, a Map becomes a Deck of `[key, value]` pairs.
+	/// Null if the Deck can't be owned.
+	pub to_deck: unsafe extern "C" fn(value: *const SwearValue) -> *mut SwearValue,
+}
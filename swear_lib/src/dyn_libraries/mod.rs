@@ -1,22 +1,326 @@
-use std::{path::Path, sync::Arc};
+/// The interface plugins are built against, safe to use from any compiler or language.
+pub mod abi;
+/// Conversions for writing plugins in Rust, used by [`swear_dyn_api`].
+pub mod plugin;
+
+pub use swear_lib_macros::swear_dyn_api;
+
+use std::collections::BTreeSet as HashSet;
+use std::ffi::c_void;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
 use libloading::Library;
 
-use crate::{context::ObjectRef, object::{self, Object}};
+use crate::context::ObjectRef;
+use crate::object::{self, Chars, Count, Deck, FunctionInfoBuilder, IObject, Map, Object, State, Zip};
+use abi::*;
+
+/// Why a plugin couldn't be loaded.
+#[derive(Debug)]
+pub enum PluginError {
+	/// The library couldn't be opened.
+	Load(libloading::Error),
+	/// The library doesn't export one of the plugin symbols, named here.
+	Missing(String),
+	/// The plugin was built against a different version of the interface.
+	Incompatible {
+		expected: u32,
+		found: u32,
+	},
+	/// The plugin's init function reported a failure.
+	InitFailed,
+}
+
+impl std::fmt::Display for PluginError {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		match self {
+			PluginError::Load(e) => write!(f, "{e}"),
+			PluginError::Missing(symbol) => write!(f, "library doesn't export `{symbol}`"),
+			PluginError::Incompatible { expected, found } => write!(f, "plugin was built for ABI version {found}, expected {expected}"),
+			PluginError::InitFailed => write!(f, "plugin failed to initialise"),
+		}
+	}
+}
+
+impl std::error::Error for PluginError {}
+
+impl From<libloading::Error> for PluginError {
+	fn from(e: libloading::Error) -> Self {
+		PluginError::Load(e)
+	}
+}
 
-// Functions expected to be present in the dynamic library.
-const INIT_FUNC: &[u8] = b"init\0";
-type InitFunc = unsafe extern fn(&mut object::Dynamic);
+/// Finds a library in `search_path`, then next to the executable.
+/// `name` may leave out the platform's prefix and extension, so `foo` finds `libfoo.so` on Linux.
+pub fn find_library(name: &str, search_path: &[PathBuf]) -> Option<PathBuf> {
+	use std::env::consts::{DLL_PREFIX, DLL_SUFFIX};
 
-pub fn load_library(path: &Path) -> Result<ObjectRef, libloading::Error> {
-	let lib: Library;
-	let mut object = object::Dynamic::default();
+	let candidates = [
+		name.to_string(),
+		format!("{name}{DLL_SUFFIX}"),
+		format!("{DLL_PREFIX}{name}{DLL_SUFFIX}"),
+	];
+	let exe_dir = std::env::current_exe().ok().and_then(|exe| exe.parent().map(Path::to_path_buf));
+
+	search_path.iter()
+		.chain(exe_dir.as_ref())
+		.flat_map(|dir| candidates.iter().map(move |file| dir.join(file)))
+		.find(|path| path.is_file())
+		.and_then(|path| path.canonicalize().ok())
+}
+
+/// Loads a plugin, returning a Dynamic holding the functions it registered.
+/// The Dynamic is named after the library's file.
+pub fn load_library<'rt>(path: &Path) -> Result<ObjectRef<'rt>, PluginError> {
+	let name = path.file_stem().map(|stem| stem.to_string_lossy().into_owned()).unwrap_or_default();
+	let mut object = object::Dynamic::named(name);
+
+	let lib = Arc::new(unsafe { Library::new(path)? });
 
 	unsafe {
-		lib = Library::new(path)?;
-		let func = lib.get::<InitFunc>(INIT_FUNC)?;
-		func(&mut object);
+		let version = symbol::<unsafe extern "C" fn() -> u32>(&lib, ABI_VERSION_SYMBOL)?;
+		let found = version();
+		if found != ABI_VERSION {
+			return Err(PluginError::Incompatible { expected: ABI_VERSION, found });
+		}
+
+		//? Every registered function keeps its own hold on the library, they can outlive this Object.
+		object.src_lib = Some(lib.clone());
+
+		let init = symbol::<SwearPluginInit>(&lib, INIT_SYMBOL)?;
+		if !init(&HOST, &mut object as *mut object::Dynamic as *mut SwearPlugin) {
+			return Err(PluginError::InitFailed);
+		}
 	}
-	
-	object.src_lib = Some(Arc::new(lib));
+
 	Ok(Object::from(object).into())
 }
+
+/// Looks up one of the plugin symbols, naming it if the library doesn't export it.
+unsafe fn symbol<'lib, T>(lib: &'lib Library, name: &'static [u8]) -> Result<libloading::Symbol<'lib, T>, PluginError> {
+	unsafe { lib.get(name) }.map_err(|_| {
+		let name = name.strip_suffix(b"\0").unwrap_or(name);
+		PluginError::Missing(String::from_utf8_lossy(name).into_owned())
+	})
+}
+
+//? Handles are boxed ObjectRefs. The host functions can't carry the runtime's lifetime,
+//? so they see everything as 'static.
+fn handle<'rt>(obj: ObjectRef<'rt>) -> *mut SwearValue {
+	Box::into_raw(Box::new(obj)) as *mut SwearValue
+}
+
+//? A plugin can keep the handles it owns for as long as it likes, even past the runtime.
+//? Dynamics and Callables hold callbacks borrowing the runtime's script, so rather than
+//? handing out a handle that could outlive them, there's no handle at all.
+fn owned_handle<'rt>(obj: ObjectRef<'rt>) -> *mut SwearValue {
+	match detached(&obj) {
+		true => handle(obj),
+		false => std::ptr::null_mut(),
+	}
+}
+
+/// Whether nothing in the Object borrows from the runtime, looking through Decks and Maps.
+fn detached(obj: &ObjectRef<'_>) -> bool {
+	let mut seen = HashSet::new();
+	let mut pending = vec![obj.copy()];
+
+	while let Some(obj) = pending.pop() {
+		//? A Deck can hold itself.
+		if !seen.insert(Arc::as_ptr(&obj.copy().inner()) as usize) {
+			continue;
+		}
+		match &*obj.access() {
+			Object::Dynamic(_) | Object::Callable(_) => return false,
+			Object::Deck(deck) => pending.extend(deck.deck.iter().map(ObjectRef::copy)),
+			Object::Map(map) => pending.extend(map.map.iter().flat_map(|(key, value)| [key.copy(), value.copy()])),
+			_ => (),
+		}
+	}
+	true
+}
+
+unsafe fn borrow<'a>(value: *const SwearValue) -> &'a ObjectRef<'static> {
+	unsafe { &*(value as *const ObjectRef<'static>) }
+}
+
+unsafe fn take<'rt>(value: *mut SwearValue) -> ObjectRef<'rt> {
+	unsafe { *Box::from_raw(value as *mut ObjectRef<'rt>) }
+}
+
+unsafe fn borrow_all<'a>(values: *const *const SwearValue, len: usize) -> impl Iterator<Item = ObjectRef<'static>> + 'a {
+	let values = match len {
+		0 => &[],
+		_ => unsafe { std::slice::from_raw_parts(values, len) },
+	};
+	values.iter().map(|value| unsafe { borrow(*value) }.copy())
+}
+
+//? Unwinding out of an extern "C" function aborts, so a panic becomes the function's error value instead.
+fn guard<T>(failed: T, body: impl FnOnce() -> T) -> T {
+	std::panic::catch_unwind(std::panic::AssertUnwindSafe(body)).unwrap_or(failed)
+}
+
+/// A registered function, along with the data it was registered with.
+struct PluginFunction {
+	function: SwearPluginFn,
+	data: *mut c_void,
+	drop_data: Option<SwearDropFn>,
+	_lib: Option<Arc<Library>>,
+}
+
+impl Drop for PluginFunction {
+	fn drop(&mut self) {
+		if let Some(drop_data) = self.drop_data {
+			unsafe { drop_data(self.data) }
+		}
+	}
+}
+
+static HOST: SwearHost = SwearHost {
+	abi_version: ABI_VERSION,
+	size: size_of::<SwearHost>(),
+	register,
+	kind,
+	to_state,
+	to_count,
+	to_int,
+	read_chars,
+	len,
+	get,
+	new_zip,
+	new_state,
+	new_count,
+	new_int,
+	new_chars,
+	new_deck,
+	new_map,
+	share,
+	release,
+	to_deck,
+};
+
+unsafe extern "C" fn register(plugin: *mut SwearPlugin, name: SwearStr, function: SwearPluginFn, data: *mut c_void, drop_data: Option<SwearDropFn>) {
+	guard((), || {
+		let plugin = unsafe { &mut *(plugin as *mut object::Dynamic<'static>) };
+		let name = unsafe { name.to_str() }.into_owned();
+		let function = PluginFunction { function, data, drop_data, _lib: plugin.src_lib.clone() };
+
+		plugin.add_function(FunctionInfoBuilder::new(name).build_native(Arc::new(Mutex::new(move |obj: ObjectRef<'static>, args: Vec<ObjectRef<'static>>, _| {
+			//? Borrowing the whole thing moves it into the closure, rather than just the fields used.
+			let function = &function;
+			let target = handle(obj);
+			let args: Vec<*const SwearValue> = args.into_iter().map(|arg| handle(arg) as *const SwearValue).collect();
+
+			let result = unsafe { (function.function)(function.data, &HOST, target, args.as_ptr(), args.len()) };
+
+			unsafe {
+				release(target);
+				args.into_iter().for_each(|arg| release(arg as *mut SwearValue));
+			}
+
+			match result.is_null() {
+				true => Err(()),
+				false => Ok(Some(unsafe { take(result) })),
+			}
+		}))));
+	})
+}
+
+unsafe extern "C" fn kind(value: *const SwearValue) -> SwearKind {
+	guard(SwearKind::Zip, || match &*unsafe { borrow(value) }.access() {
+		Object::Zip(_) => SwearKind::Zip,
+		Object::State(_) => SwearKind::State,
+		Object::Count(_) => SwearKind::Count,
+		Object::Chars(_) => SwearKind::Chars,
+		Object::Deck(_) => SwearKind::Deck,
+		Object::Map(_) => SwearKind::Map,
+		Object::Dynamic(_) => SwearKind::Dynamic,
+		Object::Callable(_) => SwearKind::Callable,
+	})
+}
+
+unsafe extern "C" fn to_state(value: *const SwearValue) -> bool {
+	guard(false, || unsafe { borrow(value) }.access().to_state().state)
+}
+
+unsafe extern "C" fn to_count(value: *const SwearValue) -> f64 {
+	guard(0.0, || unsafe { borrow(value) }.access().to_count().to_f64())
+}
+
+unsafe extern "C" fn to_int(value: *const SwearValue) -> i64 {
+	guard(0, || unsafe { borrow(value) }.access().to_count().to_i64())
+}
+
+unsafe extern "C" fn read_chars(value: *const SwearValue, buffer: *mut u8, capacity: usize) -> usize {
+	guard(0, || {
+		let chars = unsafe { borrow(value) }.access().to_chars().chars;
+		if !buffer.is_null() {
+			unsafe { std::ptr::copy_nonoverlapping(chars.as_ptr(), buffer, chars.len().min(capacity)) };
+		}
+		chars.len()
+	})
+}
+
+unsafe extern "C" fn to_deck(value: *const SwearValue) -> *mut SwearValue {
+	guard(std::ptr::null_mut(), || owned_handle(Object::from(unsafe { borrow(value) }.access().to_deck()).into()))
+}
+
+unsafe extern "C" fn len(value: *const SwearValue) -> usize {
+	guard(0, || unsafe { borrow(value) }.access().to_deck().deck.len())
+}
+
+unsafe extern "C" fn get(value: *const SwearValue, key: *const SwearValue) -> *mut SwearValue {
+	guard(std::ptr::null_mut(), || {
+		let key = unsafe { borrow(key) };
+		let item = match &*unsafe { borrow(value) }.access() {
+			Object::Deck(deck) => deck.index(&key.access().to_count()).map(|index| deck.deck[index].copy()),
+			Object::Map(map) => map.map.get(key).map(ObjectRef::copy),
+			_ => None,
+		};
+		item.map_or(std::ptr::null_mut(), owned_handle)
+	})
+}
+
+unsafe extern "C" fn new_zip() -> *mut SwearValue {
+	guard(std::ptr::null_mut(), || handle(Object::from(Zip).into()))
+}
+
+unsafe extern "C" fn new_state(state: bool) -> *mut SwearValue {
+	guard(std::ptr::null_mut(), || handle(Object::from(State::from(state)).into()))
+}
+
+unsafe extern "C" fn new_count(count: f64) -> *mut SwearValue {
+	guard(std::ptr::null_mut(), || handle(Object::from(Count::from(count)).into()))
+}
+
+unsafe extern "C" fn new_int(count: i64) -> *mut SwearValue {
+	guard(std::ptr::null_mut(), || handle(Object::from(Count::from(count)).into()))
+}
+
+unsafe extern "C" fn new_chars(chars: SwearStr) -> *mut SwearValue {
+	guard(std::ptr::null_mut(), || handle(Object::from(Chars::from(unsafe { chars.to_str() }.into_owned())).into()))
+}
+
+unsafe extern "C" fn new_deck(items: *const *const SwearValue, len: usize) -> *mut SwearValue {
+	guard(std::ptr::null_mut(), || owned_handle(Object::from(Deck::from_iter_ref(unsafe { borrow_all(items, len) })).into()))
+}
+
+unsafe extern "C" fn new_map(keys: *const *const SwearValue, values: *const *const SwearValue, len: usize) -> *mut SwearValue {
+	guard(std::ptr::null_mut(), || {
+		let pairs = unsafe { borrow_all(keys, len).zip(borrow_all(values, len)) };
+		owned_handle(Object::from(pairs.collect::<Map>()).into())
+	})
+}
+
+unsafe extern "C" fn share(value: *const SwearValue) -> *mut SwearValue {
+	guard(std::ptr::null_mut(), || owned_handle(unsafe { borrow(value) }.copy()))
+}
+
+unsafe extern "C" fn release(value: *mut SwearValue) {
+	guard((), || {
+		if !value.is_null() {
+			drop(unsafe { take(value) });
+		}
+	})
+}
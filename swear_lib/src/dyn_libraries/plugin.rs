@@ -0,0 +1,352 @@
+use std::collections::{BTreeMap, HashMap};
+use std::hash::Hash;
+
+use super::abi::*;
+
+/// A Rust value a plugin function can take as an argument.
+///
+/// The plugin side counterpart of [`FromSwear`](crate::object::FromSwear), covering the same types.
+pub trait FromValue: Sized {
+	/// # Safety
+	/// `value` must be a live handle given out by `host`.
+	unsafe fn from_value(host: &SwearHost, value: *const SwearValue) -> Option<Self>;
+
+	/// Used in place of an argument that wasn't given, by default the call fails.
+	fn missing() -> Option<Self> {
+		None
+	}
+}
+
+/// A Rust value a plugin function can return.
+///
+/// The plugin side counterpart of [`IntoSwear`](crate::object::IntoSwear), covering the same types.
+pub trait IntoValue {
+	/// Returns an owned handle, or null if the function failed.
+	fn into_value(self, host: &SwearHost) -> *mut SwearValue;
+}
+
+impl FromValue for bool {
+	unsafe fn from_value(host: &SwearHost, value: *const SwearValue) -> Option<Self> {
+		Some(unsafe { (host.to_state)(value) })
+	}
+}
+
+impl FromValue for f64 {
+	unsafe fn from_value(host: &SwearHost, value: *const SwearValue) -> Option<Self> {
+		Some(unsafe { (host.to_count)(value) })
+	}
+}
+
+impl FromValue for f32 {
+	unsafe fn from_value(host: &SwearHost, value: *const SwearValue) -> Option<Self> {
+		Some(unsafe { (host.to_count)(value) } as f32)
+	}
+}
+
+impl FromValue for String {
+	unsafe fn from_value(host: &SwearHost, value: *const SwearValue) -> Option<Self> {
+		let len = unsafe { (host.read_chars)(value, std::ptr::null_mut(), 0) };
+		let mut buffer = vec![0; len];
+		unsafe { (host.read_chars)(value, buffer.as_mut_ptr(), len) };
+		String::from_utf8(buffer).ok()
+	}
+}
+
+/// Anything fits.
+impl FromValue for () {
+	unsafe fn from_value(_: &SwearHost, _: *const SwearValue) -> Option<Self> {
+		Some(())
+	}
+}
+
+/// A handle of its own to the very same Object, for passing it along untouched.
+/// It has to be returned or given to `release`.
+/// Dynamics and Callables can't be owned, so don't fit, and neither do Decks or Maps holding them.
+impl FromValue for *mut SwearValue {
+	unsafe fn from_value(host: &SwearHost, value: *const SwearValue) -> Option<Self> {
+		let value = unsafe { (host.share)(value) };
+		(!value.is_null()).then_some(value)
+	}
+}
+
+/// Optional arguments, Zip or a missing argument are None.
+impl<T: FromValue> FromValue for Option<T> {
+	unsafe fn from_value(host: &SwearHost, value: *const SwearValue) -> Option<Self> {
+		match unsafe { (host.kind)(value) } {
+			SwearKind::Zip => Some(None),
+			_ => unsafe { T::from_value(host, value) }.map(Some),
+		}
+	}
+
+	fn missing() -> Option<Self> {
+		Some(None)
+	}
+}
+
+/// Fits if every item does.
+impl<T: FromValue> FromValue for Vec<T> {
+	unsafe fn from_value(host: &SwearHost, value: *const SwearValue) -> Option<Self> {
+		unsafe { with_deck(host, value, |deck, len| (0..len).map(|index| item(host, deck, index)).collect()) }
+	}
+}
+
+/// Fits if every key and value does.
+impl<K: FromValue + Eq + Hash, V: FromValue> FromValue for HashMap<K, V> {
+	unsafe fn from_value(host: &SwearHost, value: *const SwearValue) -> Option<Self> {
+		unsafe { Vec::<(K, V)>::from_value(host, value) }.map(|pairs| pairs.into_iter().collect())
+	}
+}
+
+impl<K: FromValue + Ord, V: FromValue> FromValue for BTreeMap<K, V> {
+	unsafe fn from_value(host: &SwearHost, value: *const SwearValue) -> Option<Self> {
+		unsafe { Vec::<(K, V)>::from_value(host, value) }.map(|pairs| pairs.into_iter().collect())
+	}
+}
+
+impl IntoValue for bool {
+	fn into_value(self, host: &SwearHost) -> *mut SwearValue {
+		unsafe { (host.new_state)(self) }
+	}
+}
+
+impl IntoValue for f64 {
+	fn into_value(self, host: &SwearHost) -> *mut SwearValue {
+		unsafe { (host.new_count)(self) }
+	}
+}
+
+impl IntoValue for f32 {
+	fn into_value(self, host: &SwearHost) -> *mut SwearValue {
+		unsafe { (host.new_count)(self as f64) }
+	}
+}
+
+impl IntoValue for String {
+	fn into_value(self, host: &SwearHost) -> *mut SwearValue {
+		self.as_str().into_value(host)
+	}
+}
+
+impl IntoValue for &str {
+	fn into_value(self, host: &SwearHost) -> *mut SwearValue {
+		unsafe { (host.new_chars)(SwearStr::new(self)) }
+	}
+}
+
+impl IntoValue for () {
+	fn into_value(self, host: &SwearHost) -> *mut SwearValue {
+		unsafe { (host.new_zip)() }
+	}
+}
+
+/// An owned handle, returned as is.
+impl IntoValue for *mut SwearValue {
+	fn into_value(self, _: &SwearHost) -> *mut SwearValue {
+		self
+	}
+}
+
+/// Nothing becomes Zip.
+impl<T: IntoValue> IntoValue for Option<T> {
+	fn into_value(self, host: &SwearHost) -> *mut SwearValue {
+		match self {
+			Some(value) => value.into_value(host),
+			None => ().into_value(host),
+		}
+	}
+}
+
+/// An error fails the call.
+impl<T: IntoValue, E> IntoValue for Result<T, E> {
+	fn into_value(self, host: &SwearHost) -> *mut SwearValue {
+		match self {
+			Ok(value) => value.into_value(host),
+			Err(_) => std::ptr::null_mut(),
+		}
+	}
+}
+
+impl<T: IntoValue> IntoValue for Vec<T> {
+	fn into_value(self, host: &SwearHost) -> *mut SwearValue {
+		let Some(items) = handles(host, self) else {
+			return std::ptr::null_mut();
+		};
+		let deck = unsafe { (host.new_deck)(items.as_ptr() as *const *const SwearValue, items.len()) };
+		release_all(host, items);
+		deck
+	}
+}
+
+impl<K: IntoValue, V: IntoValue> IntoValue for HashMap<K, V> {
+	fn into_value(self, host: &SwearHost) -> *mut SwearValue {
+		map_value(host, self)
+	}
+}
+
+impl<K: IntoValue, V: IntoValue> IntoValue for BTreeMap<K, V> {
+	fn into_value(self, host: &SwearHost) -> *mut SwearValue {
+		map_value(host, self)
+	}
+}
+
+macro_rules! int_value {
+	($($int:ty),*) => {
+		$(
+			//? Out of range counts don't fit, rather than wrapping around.
+			impl FromValue for $int {
+				unsafe fn from_value(host: &SwearHost, value: *const SwearValue) -> Option<Self> {
+					<$int>::try_from(unsafe { (host.to_int)(value) }).ok()
+				}
+			}
+
+			impl IntoValue for $int {
+				fn into_value(self, host: &SwearHost) -> *mut SwearValue {
+					match i64::try_from(self) {
+						Ok(int) => unsafe { (host.new_int)(int) },
+						Err(_) => unsafe { (host.new_count)(self as f64) },
+					}
+				}
+			}
+		)*
+	};
+}
+
+int_value!(i8, i16, i32, i64, i128, isize, u8, u16, u32, u64, u128, usize);
+
+macro_rules! tuple_value {
+	($(($($item:ident),+)),*) => {
+		$(
+			/// A Deck with exactly one item for each element.
+			impl<$($item: FromValue),+> FromValue for ($($item,)+) {
+				unsafe fn from_value(host: &SwearHost, value: *const SwearValue) -> Option<Self> {
+					unsafe {
+						with_deck(host, value, |deck, len| {
+							let mut indices = 0..len;
+							let tuple = ($(item::<$item>(host, deck, indices.next()?)?,)+);
+							indices.next().is_none().then_some(tuple)
+						})
+					}
+				}
+			}
+
+			#[allow(non_snake_case)]
+			impl<$($item: IntoValue),+> IntoValue for ($($item,)+) {
+				fn into_value(self, host: &SwearHost) -> *mut SwearValue {
+					let ($($item,)+) = self;
+					let items = [$($item.into_value(host)),+];
+
+					let deck = match items.iter().any(|item| item.is_null()) {
+						true => std::ptr::null_mut(),
+						false => unsafe { (host.new_deck)(items.as_ptr() as *const *const SwearValue, items.len()) },
+					};
+					release_all(host, items);
+					deck
+				}
+			}
+		)*
+	};
+}
+
+tuple_value!((A), (A, B), (A, B, C), (A, B, C, D), (A, B, C, D, E), (A, B, C, D, E, F));
+
+/// Reads `value` as a Deck, along with how many items it has.
+/// Hosts from before `to_deck` was added don't fit anything.
+unsafe fn with_deck<R>(host: &SwearHost, value: *const SwearValue, read: impl FnOnce(*const SwearValue, usize) -> Option<R>) -> Option<R> {
+	if host.size <= std::mem::offset_of!(SwearHost, to_deck) {
+		return None;
+	}
+
+	let deck = unsafe { (host.to_deck)(value) };
+	if deck.is_null() {
+		return None;
+	}
+	let result = read(deck, unsafe { (host.len)(deck) });
+	unsafe { (host.release)(deck) };
+	result
+}
+
+/// Reads the item at `index` of a Deck.
+unsafe fn item<T: FromValue>(host: &SwearHost, deck: *const SwearValue, index: usize) -> Option<T> {
+	let key = (index as i64).into_value(host);
+	let item = unsafe { (host.get)(deck, key) };
+	unsafe { (host.release)(key) };
+
+	if item.is_null() {
+		return None;
+	}
+	let value = unsafe { T::from_value(host, item) };
+	unsafe { (host.release)(item) };
+	value
+}
+
+/// Turns each value into a handle, or none of them if any fail.
+fn handles<T: IntoValue>(host: &SwearHost, values: impl IntoIterator<Item = T>) -> Option<Vec<*mut SwearValue>> {
+	let mut handles = Vec::new();
+	for value in values {
+		let handle = value.into_value(host);
+		if handle.is_null() {
+			release_all(host, handles);
+			return None;
+		}
+		handles.push(handle);
+	}
+	Some(handles)
+}
+
+fn release_all(host: &SwearHost, handles: impl IntoIterator<Item = *mut SwearValue>) {
+	for handle in handles {
+		unsafe { (host.release)(handle) };
+	}
+}
+
+fn map_value<K: IntoValue, V: IntoValue>(host: &SwearHost, map: impl IntoIterator<Item = (K, V)>) -> *mut SwearValue {
+	let (keys, values): (Vec<K>, Vec<V>) = map.into_iter().unzip();
+	let Some(keys) = handles(host, keys) else {
+		return std::ptr::null_mut();
+	};
+	let Some(values) = handles(host, values) else {
+		release_all(host, keys);
+		return std::ptr::null_mut();
+	};
+
+	let map = unsafe {
+		(host.new_map)(keys.as_ptr() as *const *const SwearValue, values.as_ptr() as *const *const SwearValue, keys.len())
+	};
+	release_all(host, keys);
+	release_all(host, values);
+	map
+}
+
+/// The arguments a plugin function was called with.
+pub struct Args<'a> {
+	host: &'a SwearHost,
+	args: &'a [*const SwearValue],
+}
+
+impl<'a> Args<'a> {
+	/// # Safety
+	/// `args` must point to `len` live handles given out by `host`.
+	pub unsafe fn new(host: &'a SwearHost, args: *const *const SwearValue, len: usize) -> Self {
+		let args = match len {
+			0 => &[],
+			_ => unsafe { std::slice::from_raw_parts(args, len) },
+		};
+		Self { host, args }
+	}
+
+	/// The argument at `index` as a `T`, if it converts.
+	pub fn get<T: FromValue>(&self, index: usize) -> Option<T> {
+		match self.args.get(index) {
+			Some(value) => unsafe { T::from_value(self.host, *value) },
+			None => T::missing(),
+		}
+	}
+
+	pub fn len(&self) -> usize {
+		self.args.len()
+	}
+
+	pub fn is_empty(&self) -> bool {
+		self.args.is_empty()
+	}
+}
@@ -6,4 +6,4 @@ pub mod runtime;
 pub mod dyn_libraries;
 
 pub use swear_parser;
-pub type BigNum = dashu_float::FBig<dashu_float::round::mode::Zero, 2>;
+pub type BigNum = dashu_float::FBig<dashu_float::round::mode::HalfAway, 10>;
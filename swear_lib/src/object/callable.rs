@@ -0,0 +1,164 @@
+use super::*;
+
+/// An Object that holds a callback, so it can be stored and passed around without calling it.
+///
+/// Made with `!name`, and called like any other callback, or with its `call` function.
+#[derive(Clone)]
+pub struct Callable<'rt> {
+	pub name: String,
+	pub callback: Callback<'rt>,
+}
+
+impl<'rt> Callable<'rt> {
+	pub fn new(name: String, callback: Callback<'rt>) -> Self {
+		Self { name, callback }
+	}
+
+	/// The number of arguments the callback takes.
+	pub fn arg_count(&self) -> usize {
+		match &self.callback {
+			Callback::Swear(callback) => callback.args.len(),
+			Callback::Native(callback) => callback.arg_count,
+		}
+	}
+
+	/// What tells the held callback apart from any other, the same for every copy of it.
+	/// A Swear callback is its body along with the scope it was defined in.
+	fn identity(&self) -> (u8, usize, usize) {
+		match &self.callback {
+			Callback::Swear(callback) => (0, std::ptr::from_ref(callback.callback) as usize, callback.scope.addr()),
+			Callback::Native(callback) => (1, Arc::as_ptr(&callback.callback).cast::<()>() as usize, 0),
+		}
+	}
+}
+
+//? Two Callables are only equal if they hold the very same callback.
+impl<'rt> PartialEq for Callable<'rt> {
+	fn eq(&self, other: &Self) -> bool {
+		self.name == other.name && self.identity() == other.identity()
+	}
+}
+
+impl<'rt> Eq for Callable<'rt> {}
+
+impl<'rt> PartialOrd for Callable<'rt> {
+	fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+		Some(self.cmp(other))
+	}
+}
+
+//? By name first so sorting is readable, then by identity to agree with PartialEq.
+impl<'rt> Ord for Callable<'rt> {
+	fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+		self.name.cmp(&other.name).then_with(|| self.identity().cmp(&other.identity()))
+	}
+}
+
+impl<'rt> IObject<'rt> for Callable<'rt> {
+	fn to_chars(&self) -> Chars {
+		Chars::from(self.name.clone())
+	}
+
+	fn to_count(&self) -> Count {
+		Count::from(self.arg_count())
+	}
+
+	fn to_state(&self) -> State {
+		State::from(true)
+	}
+
+	fn to_deck(&self) -> Deck<'rt> {
+		Deck::default()
+	}
+
+	fn to_map(&self) -> Map<'rt> {
+		Map::default()
+	}
+
+	fn get_info(&self) -> ObjectInfo {
+		ObjectInfo::from_str("Callable")
+			.with_description_str("An Object that holds a callback.")
+	}
+
+	fn get_function(&self, name: &str) -> Option<FunctionInfo<'rt>> {
+		Some(match name {
+			// Call function.
+			// Calls the held callback with the arguments.
+			"call" => FunctionInfoBuilder::new("call".to_string()).build(self.callback.clone()),
+
+			// Equals function.
+			// Returns true if all arguments hold the same callback as this object.
+			"equals" =>
+				FunctionInfoBuilder::new("equals".to_string()).build_native(Arc::new(Mutex::new(|obj: ObjectRef<'rt>, args: Vec<ObjectRef<'rt>>, _| {
+					let obj = obj.access();
+					let all = args.iter().all(|arg| *arg.access() == *obj);
+
+					Ok(Some(Object::from(State::from(all)).into()))
+				}))),
+
+			// Lest function.
+			// No op, returns this Object.
+			"lest" => FunctionInfoBuilder::new("lest".to_string()).build_native(Arc::new(Mutex::new(|obj, _, _| Ok(Some(obj))))),
+
+			// Solid function.
+			// Returns false if Zip.
+			"solid" => FunctionInfoBuilder::new("solid".to_string()).build_native(Arc::new(Mutex::new(|_, _, _| Ok(Some(Object::from(State::from(true)).into()))))),
+
+			_ => return None,
+		})
+	}
+}
+
+impl<'rt> std::fmt::Debug for Callable<'rt> {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		write!(f, "Callable({})", self.name)
+	}
+}
+
+#[cfg(feature="serde")]
+mod serde_impl {
+	// Callbacks can't be written out, so only the name is kept.
+	use super::*;
+	use serde::{Serialize, Serializer};
+
+	impl<'rt> Serialize for Callable<'rt> {
+		fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+			serializer.serialize_str(&self.name)
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::context::NativeCallback;
+
+	fn native(name: &str) -> Callable<'static> {
+		Callable::new(name.to_string(), Callback::Native(NativeCallback {
+			arg_count: 0,
+			callback: Arc::new(Mutex::new(|_, _, _| Ok(None))),
+		}))
+	}
+
+	/// Test that Callables only compare equal when they hold the same callback, and order the same way.
+	#[test]
+	fn test_identity() {
+		let a = native("same");
+		let b = native("same");
+
+		assert_eq!(a, a.clone());
+		assert_eq!(a.cmp(&a.clone()), std::cmp::Ordering::Equal);
+
+		assert_ne!(a, b);
+		assert_ne!(a.cmp(&b), std::cmp::Ordering::Equal);
+		assert_eq!(a.cmp(&b), b.cmp(&a).reverse());
+
+		//? Names still come first.
+		assert_eq!(native("a").cmp(&native("b")), std::cmp::Ordering::Less);
+
+		let (a, b): (ObjectRef, ObjectRef) = (Object::from(a).into(), Object::from(b).into());
+		assert_eq!(a, ObjectRef::new(a.access().clone()));
+		assert_eq!(a.cmp(&ObjectRef::new(a.access().clone())), std::cmp::Ordering::Equal);
+		assert_ne!(a.cmp(&b), std::cmp::Ordering::Equal);
+	}
+}
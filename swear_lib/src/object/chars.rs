@@ -1,11 +1,13 @@
-use crate::dyn_libraries;
-use std::{env::current_exe, fmt::Debug};
+use std::fmt::Debug;
 
 use super::*;
 
+/// The longest Chars `repeat` will make, in bytes.
+pub const MAX_REPEAT_LEN: usize = 1 << 30;
+
 /// An object that represents a collection of zero or more unicode characters.
 #[cfg_attr(feature="serde", derive(serde::Serialize, serde::Deserialize))]
-#[derive(Clone, Default, PartialEq, Eq)]
+#[derive(Clone, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
 #[swear_object]
 pub struct Chars {
 	pub chars: String,
@@ -17,7 +19,7 @@ impl<'rt> Chars {
 	}
 
 	fn to_swear_count(&self) -> Count {
-		Count { count: self.chars.parse().unwrap_or_else(|_| self.chars.len() as i64) }
+		Count::parse(&self.chars).unwrap_or_else(|| Count::from(self.chars.chars().count()))
 	}
 
 	fn to_swear_state(&self) -> State {
@@ -37,7 +39,7 @@ impl<'rt> Chars {
 			// Scribe function.
 			// Prints the characters to the console.
 			"scribe" =>
-				FunctionInfoBuilder::new("scribe".to_string()).build_native(Arc::new(Mutex::new(|obj: ObjectRef<'rt>, _| {
+				FunctionInfoBuilder::new("scribe".to_string()).build_native(Arc::new(Mutex::new(|obj: ObjectRef<'rt>, _, _| {
 					let lock = obj.access();
 					println!("{}", lock.to_chars().chars);
 					Ok(None)
@@ -46,7 +48,7 @@ impl<'rt> Chars {
 			// Concat function.
 			// Takes any number of arguments and concatenates them into a single string separated by the method target.
 			"concat" =>
-				FunctionInfoBuilder::new("concat".to_string()).build_native(Arc::new(Mutex::new(|obj: ObjectRef<'rt>, args: Vec<ObjectRef<'rt>>| {
+				FunctionInfoBuilder::new("concat".to_string()).build_native(Arc::new(Mutex::new(|obj: ObjectRef<'rt>, args: Vec<ObjectRef<'rt>>, _| {
 					let target = obj.access();
 					let mut result = String::new();
 					let mut iter = args.iter();
@@ -64,15 +66,15 @@ impl<'rt> Chars {
 			// Size function.
 			// Returns the number of characters in the string.
 			"size" =>
-				FunctionInfoBuilder::new("size".to_string()).build_native(Arc::new(Mutex::new(|obj: ObjectRef<'rt>, _| {
+				FunctionInfoBuilder::new("size".to_string()).build_native(Arc::new(Mutex::new(|obj: ObjectRef<'rt>, _, _| {
 					let lock = obj.access();
-					Ok(Some(Object::from(Count::from(lock.to_chars().chars.len())).into()))
+					Ok(Some(Object::from(Count::from(lock.to_chars().chars.chars().count())).into()))
 				}))),
 		
 			// Assign function.
 			// Replaces the value of the Chars in place.
 			"assign" =>
-				FunctionInfoBuilder::new("assign".to_string()).build_native(Arc::new(Mutex::new(|obj: ObjectRef<'rt>, args: Vec<ObjectRef<'rt>>| {
+				FunctionInfoBuilder::new("assign".to_string()).build_native(Arc::new(Mutex::new(|obj: ObjectRef<'rt>, args: Vec<ObjectRef<'rt>>, _| {
 					let mut lock = obj.lock();
 					let new_value = args.get(0).ok_or(())?.access();
 					lock.as_chars_mut().unwrap().chars = new_value.to_chars().chars;
@@ -83,18 +85,178 @@ impl<'rt> Chars {
 			// Lest function.
 			// No op, returns this Object.
 			"lest" =>
-				FunctionInfoBuilder::new("lest".to_string()).build_native(Arc::new(Mutex::new(|obj, _| Ok(Some(obj))))),
+				FunctionInfoBuilder::new("lest".to_string()).build_native(Arc::new(Mutex::new(|obj, _, _| Ok(Some(obj))))),
 		
 			// Solid function.
 			// Returns false if Zip.
 			"solid" =>
-				FunctionInfoBuilder::new("solid".to_string()).build_native(Arc::new(Mutex::new(|_, _| Ok(Some(Object::from(State::from(true)).into()))))),
+				FunctionInfoBuilder::new("solid".to_string()).build_native(Arc::new(Mutex::new(|_, _, _| Ok(Some(Object::from(State::from(true)).into()))))),
+
+			// Substring function.
+			// Returns the characters from the first index up to, but not including, the second.
+			// Without a second index it runs to the end. Negative indices count from the end, out of range ones are clamped.
+			"substring" =>
+				FunctionInfoBuilder::new("substring".to_string()).build_native(Arc::new(Mutex::new(|obj: ObjectRef<'rt>, args: Vec<ObjectRef<'rt>>, _| {
+					let bounds: Vec<Count> = args.iter().map(|arg| arg.access().to_count()).collect();
+					let chars = obj.access().to_chars().chars;
+
+					let len = chars.chars().count();
+					let start = bounds.first().map(|b| clamp_index(b, len)).unwrap_or(0);
+					let end = bounds.get(1).map(|b| clamp_index(b, len)).unwrap_or(len).max(start);
+
+					Ok(Some(Object::from(Chars::from(chars.chars().skip(start).take(end - start).collect::<String>())).into()))
+				}))),
+
+			// Char at function.
+			// Returns the character at the given index, or Zip if there isn't one.
+			"char_at" =>
+				FunctionInfoBuilder::new("char_at".to_string()).build_native(Arc::new(Mutex::new(|obj: ObjectRef<'rt>, args: Vec<ObjectRef<'rt>>, _| {
+					let index = args.first().ok_or(())?.access().to_count().to_i64();
+					let chars = obj.access().to_chars().chars;
+
+					let len = chars.chars().count() as i64;
+					let index = if index < 0 { len + index } else { index };
+					let found = usize::try_from(index).ok().and_then(|i| chars.chars().nth(i));
+
+					Ok(Some(match found {
+						Some(c) => Object::from(Chars::from(c.to_string())),
+						None => Object::from(Zip),
+					}.into()))
+				}))),
+
+			// Find function.
+			// Returns the index of the first occurrence of the argument, or Zip if there isn't one.
+			"find" =>
+				FunctionInfoBuilder::new("find".to_string()).build_native(Arc::new(Mutex::new(|obj: ObjectRef<'rt>, args: Vec<ObjectRef<'rt>>, _| {
+					let needle = args.first().ok_or(())?.access().to_chars().chars;
+					let chars = obj.access().to_chars().chars;
+
+					Ok(Some(match chars.find(&needle) {
+						//? `find` gives a byte offset, scripts index by character.
+						Some(at) => Object::from(Count::from(chars[..at].chars().count())),
+						None => Object::from(Zip),
+					}.into()))
+				}))),
+
+			// Replace function.
+			// Replaces every occurrence of the first argument with the second.
+			"replace" =>
+				FunctionInfoBuilder::new("replace".to_string()).build_native(Arc::new(Mutex::new(|obj: ObjectRef<'rt>, args: Vec<ObjectRef<'rt>>, _| {
+					let [from, to] = <[_; 2]>::try_from(args).map_err(|_| ())?;
+					let (from, to) = (from.access().to_chars().chars, to.access().to_chars().chars);
+					let chars = obj.access().to_chars().chars;
+
+					Ok(Some(Object::from(Chars::from(chars.replace(&from, &to))).into()))
+				}))),
+
+			// Split function.
+			// Returns a Deck of the parts between each occurrence of the argument.
+			// Without an argument, splits on whitespace.
+			"split" =>
+				FunctionInfoBuilder::new("split".to_string()).build_native(Arc::new(Mutex::new(|obj: ObjectRef<'rt>, args: Vec<ObjectRef<'rt>>, _| {
+					let separator = args.first().map(|arg| arg.access().to_chars().chars);
+					let chars = obj.access().to_chars().chars;
+
+					let parts: Vec<Object> = match separator.as_deref() {
+						None | Some("") => chars.split_whitespace().map(|s| Chars::from(s).into()).collect(),
+						Some(separator) => chars.split(separator).map(|s| Chars::from(s).into()).collect(),
+					};
+
+					Ok(Some(Object::from(Deck::from(parts)).into()))
+				}))),
+
+			// Upper function.
+			// Returns the characters in upper case.
+			"upper" =>
+				FunctionInfoBuilder::new("upper".to_string()).build_native(Arc::new(Mutex::new(|obj: ObjectRef<'rt>, _, _| {
+					let chars = obj.access().to_chars().chars;
+					Ok(Some(Object::from(Chars::from(chars.to_uppercase())).into()))
+				}))),
+
+			// Lower function.
+			// Returns the characters in lower case.
+			"lower" =>
+				FunctionInfoBuilder::new("lower".to_string()).build_native(Arc::new(Mutex::new(|obj: ObjectRef<'rt>, _, _| {
+					let chars = obj.access().to_chars().chars;
+					Ok(Some(Object::from(Chars::from(chars.to_lowercase())).into()))
+				}))),
+
+			// Trim function.
+			// Returns the characters without leading or trailing whitespace.
+			"trim" =>
+				FunctionInfoBuilder::new("trim".to_string()).build_native(Arc::new(Mutex::new(|obj: ObjectRef<'rt>, _, _| {
+					let chars = obj.access().to_chars().chars;
+					Ok(Some(Object::from(Chars::from(chars.trim())).into()))
+				}))),
+
+			// Starts function.
+			// Returns true if the characters start with the argument.
+			"starts" =>
+				FunctionInfoBuilder::new("starts".to_string()).build_native(Arc::new(Mutex::new(|obj: ObjectRef<'rt>, args: Vec<ObjectRef<'rt>>, _| {
+					let prefix = args.first().ok_or(())?.access().to_chars().chars;
+					let chars = obj.access().to_chars().chars;
+					Ok(Some(Object::from(State::from(chars.starts_with(&prefix))).into()))
+				}))),
+
+			// Ends function.
+			// Returns true if the characters end with the argument.
+			"ends" =>
+				FunctionInfoBuilder::new("ends".to_string()).build_native(Arc::new(Mutex::new(|obj: ObjectRef<'rt>, args: Vec<ObjectRef<'rt>>, _| {
+					let suffix = args.first().ok_or(())?.access().to_chars().chars;
+					let chars = obj.access().to_chars().chars;
+					Ok(Some(Object::from(State::from(chars.ends_with(&suffix))).into()))
+				}))),
+
+			// Repeat function.
+			// Returns the characters repeated the given number of times.
+			// Fails if the result would be longer than MAX_REPEAT_LEN.
+			"repeat" =>
+				FunctionInfoBuilder::new("repeat".to_string()).build_native(Arc::new(Mutex::new(|obj: ObjectRef<'rt>, args: Vec<ObjectRef<'rt>>, _| {
+					let times = args.first().ok_or(())?.access().to_count().to_usize();
+					let chars = obj.access().to_chars().chars;
+					if chars.len().checked_mul(times).is_none_or(|len| len > MAX_REPEAT_LEN) {
+						return Err(());
+					}
+					Ok(Some(Object::from(Chars::from(chars.repeat(times))).into()))
+				}))),
+
+			// Equals function.
+			// Returns true if all arguments have the same characters.
+			"equals" => compare_all("equals", |o| o.is_eq()),
+
+			// Greater function.
+			// Returns true if the characters sort after all arguments.
+			"greater" => compare_all("greater", |o| o.is_gt()),
+
+			// Less function.
+			// Returns true if the characters sort before all arguments.
+			"less" => compare_all("less", |o| o.is_lt()),
 
 			_ => return None,
 		})
 	}
 }
 
+/// Turns a Count into a character index, negative counts index from the end.
+/// Out of range counts are clamped to the ends.
+fn clamp_index(index: &Count, len: usize) -> usize {
+	let len = len as i64;
+	let index = index.to_i64();
+	let index = if index < 0 { len + index } else { index };
+
+	index.clamp(0, len) as usize
+}
+
+/// A native that compares the target's characters against each argument's, true if `test` holds for all of them.
+fn compare_all<'rt>(name: &str, test: fn(std::cmp::Ordering) -> bool) -> FunctionInfo<'rt> {
+	FunctionInfoBuilder::new(name.to_string()).build_native(Arc::new(Mutex::new(move |obj: ObjectRef<'rt>, args: Vec<ObjectRef<'rt>>, _| {
+		let chars = obj.access().to_chars().chars;
+
+		let all = args.iter().all(|arg| test(chars.cmp(&arg.access().to_chars().chars)));
+		Ok(Some(Object::from(State::from(all)).into()))
+	})))
+}
+
 impl Debug for Chars {
 	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
 		write!(f, "Chars {:?}", self.chars)
@@ -112,3 +274,120 @@ impl From<&str> for Chars {
 		Self { chars: chars.into() }
 	}
 }
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn chars(chars: &str) -> ObjectRef<'static> {
+		Object::from(Chars::from(chars)).into()
+	}
+
+	fn count(count: i64) -> ObjectRef<'static> {
+		Object::from(Count::from(count)).into()
+	}
+
+	fn call(target: &str, name: &str, args: Vec<ObjectRef<'static>>) -> Result<ObjectRef<'static>, ()> {
+		Ok(call_native(&chars(target), name, args)?.expect("Expected a result"))
+	}
+
+	/// The Chars a call returns, or None for Zip.
+	fn text(target: &str, name: &str, args: Vec<ObjectRef<'static>>) -> Option<String> {
+		let result = call(target, name, args).expect("Failed to call");
+		let result = result.access();
+		(!result.is_zip()).then(|| result.to_chars().chars)
+	}
+
+	fn state(target: &str, name: &str, args: Vec<ObjectRef<'static>>) -> bool {
+		call(target, name, args).expect("Failed to call").access().to_state().state
+	}
+
+	/// Test that indices count characters rather than bytes, from the end when negative, and clamp when out of range.
+	#[test]
+	fn test_substring() {
+		assert_eq!(text("héllo wörld", "substring", vec![count(1), count(4)]).as_deref(), Some("éll"));
+		assert_eq!(text("héllo wörld", "substring", vec![count(7)]).as_deref(), Some("örld"));
+		assert_eq!(text("héllo wörld", "substring", vec![count(-5), count(-3)]).as_deref(), Some("wö"));
+		assert_eq!(text("héllo", "substring", vec![]).as_deref(), Some("héllo"));
+
+		assert_eq!(text("héllo", "substring", vec![count(-10), count(10)]).as_deref(), Some("héllo"));
+		assert_eq!(text("héllo", "substring", vec![count(3), count(1)]).as_deref(), Some(""));
+		assert_eq!(text("héllo", "substring", vec![count(10)]).as_deref(), Some(""));
+	}
+
+	#[test]
+	fn test_char_at() {
+		assert_eq!(text("日本語", "char_at", vec![count(1)]).as_deref(), Some("本"));
+		assert_eq!(text("日本語", "char_at", vec![count(-1)]).as_deref(), Some("語"));
+		assert_eq!(text("日本語", "char_at", vec![count(-3)]).as_deref(), Some("日"));
+
+		assert_eq!(text("日本語", "char_at", vec![count(3)]), None);
+		assert_eq!(text("日本語", "char_at", vec![count(-4)]), None);
+		assert_eq!(text("", "char_at", vec![count(0)]), None);
+		assert!(call("日本語", "char_at", vec![]).is_err());
+	}
+
+	/// Test that `find` gives a character index, even after multi-byte characters.
+	#[test]
+	fn test_find() {
+		let found = call("日本語 text", "find", vec![chars("text")]).expect("Failed to find");
+		assert_eq!(found.access().to_count(), Count::from(4));
+
+		assert!(call("日本語", "find", vec![chars("x")]).expect("Failed to find").access().is_zip());
+		assert!(call("日本語", "find", vec![]).is_err());
+	}
+
+	#[test]
+	fn test_replace() {
+		assert_eq!(text("a-b-c", "replace", vec![chars("-"), chars("→")]).as_deref(), Some("a→b→c"));
+		assert_eq!(text("abc", "replace", vec![chars("x"), chars("y")]).as_deref(), Some("abc"));
+		assert!(call("abc", "replace", vec![chars("a")]).is_err());
+	}
+
+	#[test]
+	fn test_split() {
+		let parts = |target: &str, args: Vec<ObjectRef<'static>>| -> Vec<String> {
+			let deck = call(target, "split", args).expect("Failed to split");
+			deck.access().to_deck().deck.iter().map(|part| part.access().to_chars().chars).collect()
+		};
+
+		assert_eq!(parts("a,é,,c", vec![chars(",")]), ["a", "é", "", "c"]);
+		assert_eq!(parts("  a \t b\n", vec![]), ["a", "b"]);
+		assert_eq!(parts("a b", vec![chars("")]), ["a", "b"]);
+		assert_eq!(parts("", vec![chars(",")]), [""]);
+	}
+
+	#[test]
+	fn test_case_trim() {
+		assert_eq!(text("Straße", "upper", vec![]).as_deref(), Some("STRASSE"));
+		assert_eq!(text("ÉCOLE", "lower", vec![]).as_deref(), Some("école"));
+		assert_eq!(text(" \t é \n", "trim", vec![]).as_deref(), Some("é"));
+	}
+
+	#[test]
+	fn test_starts_ends() {
+		assert!(state("héllo", "starts", vec![chars("hé")]));
+		assert!(!state("héllo", "starts", vec![chars("lo")]));
+		assert!(state("héllo", "starts", vec![chars("")]));
+		assert!(state("héllo", "ends", vec![chars("llo")]));
+		assert!(!state("héllo", "ends", vec![chars("hé")]));
+		assert!(call("héllo", "starts", vec![]).is_err());
+		assert!(call("héllo", "ends", vec![]).is_err());
+	}
+
+	/// Test that `repeat` fails rather than making Chars longer than MAX_REPEAT_LEN bytes.
+	#[test]
+	fn test_repeat() {
+		assert_eq!(text("ab", "repeat", vec![count(3)]).as_deref(), Some("ababab"));
+		assert_eq!(text("ab", "repeat", vec![count(0)]).as_deref(), Some(""));
+		assert_eq!(text("ab", "repeat", vec![count(-2)]).as_deref(), Some(""));
+		assert_eq!(text("", "repeat", vec![count(i64::MAX)]).as_deref(), Some(""));
+
+		//? The cap counts bytes, and `é` takes two.
+		let times = (MAX_REPEAT_LEN / 2 + 1) as i64;
+		assert!(call("é", "repeat", vec![count(times)]).is_err());
+		assert!(call("ab", "repeat", vec![count(times)]).is_err());
+		assert!(call("ab", "repeat", vec![count(i64::MAX)]).is_err());
+		assert!(call("ab", "repeat", vec![]).is_err());
+	}
+}
@@ -0,0 +1,386 @@
+use std::collections::{BTreeMap, HashMap};
+use std::hash::Hash;
+
+use super::*;
+
+/// A Rust value that can be read out of an Object.
+///
+/// Objects are converted the same way Swear converts them, so `'12'$` reads as a number
+/// and anything reads as a `bool`.
+pub trait FromSwear<'rt>: Sized {
+	/// Returns None if the Object doesn't fit, like a negative Count for an unsigned integer.
+	fn from_swear(obj: &ObjectRef<'rt>) -> Option<Self>;
+
+	/// Used in place of an argument that wasn't given, by default there's nothing to use.
+	fn missing() -> Option<Self> {
+		None
+	}
+}
+
+/// A Rust value that can be turned into an Object.
+pub trait IntoSwear<'rt> {
+	fn into_swear(self) -> ObjectRef<'rt>;
+}
+
+/// Reads the argument at `index` for a native callback, failing the callback if it doesn't fit.
+///
+/// ```ignore
+/// |_, args, _| {
+///     let times: usize = from_arg(&args, 0)?;
+///     let text: Option<String> = from_arg(&args, 1)?;
+///     Ok(Some(text.unwrap_or_default().repeat(times).into_swear()))
+/// }
+/// ```
+pub fn from_arg<'rt, T: FromSwear<'rt>>(args: &[ObjectRef<'rt>], index: usize) -> Result<T, ()> {
+	match args.get(index) {
+		Some(arg) => T::from_swear(arg),
+		None => T::missing(),
+	}.ok_or(())
+}
+
+impl<'rt> FromSwear<'rt> for ObjectRef<'rt> {
+	fn from_swear(obj: &ObjectRef<'rt>) -> Option<Self> {
+		Some(obj.copy())
+	}
+}
+
+impl<'rt> IntoSwear<'rt> for ObjectRef<'rt> {
+	fn into_swear(self) -> ObjectRef<'rt> {
+		self
+	}
+}
+
+impl<'rt> IntoSwear<'rt> for Object<'rt> {
+	fn into_swear(self) -> ObjectRef<'rt> {
+		self.into()
+	}
+}
+
+macro_rules! object_swear {
+	($($obj:ty => $to:ident),*) => {
+		$(
+			impl<'rt> FromSwear<'rt> for $obj {
+				fn from_swear(obj: &ObjectRef<'rt>) -> Option<Self> {
+					Some(obj.access().$to())
+				}
+			}
+
+			impl<'rt> IntoSwear<'rt> for $obj {
+				fn into_swear(self) -> ObjectRef<'rt> {
+					Object::from(self).into()
+				}
+			}
+		)*
+	};
+}
+
+object_swear!(Chars => to_chars, Count => to_count, State => to_state, Zip => to_zip, Deck<'rt> => to_deck, Map<'rt> => to_map);
+
+impl<'rt> FromSwear<'rt> for bool {
+	fn from_swear(obj: &ObjectRef<'rt>) -> Option<Self> {
+		Some(obj.access().to_state().state)
+	}
+}
+
+impl<'rt> IntoSwear<'rt> for bool {
+	fn into_swear(self) -> ObjectRef<'rt> {
+		State::from(self).into_swear()
+	}
+}
+
+impl<'rt> FromSwear<'rt> for String {
+	fn from_swear(obj: &ObjectRef<'rt>) -> Option<Self> {
+		Some(obj.access().to_chars().chars)
+	}
+}
+
+impl<'rt> IntoSwear<'rt> for String {
+	fn into_swear(self) -> ObjectRef<'rt> {
+		Chars::from(self).into_swear()
+	}
+}
+
+impl<'rt> IntoSwear<'rt> for &str {
+	fn into_swear(self) -> ObjectRef<'rt> {
+		Chars::from(self).into_swear()
+	}
+}
+
+impl<'rt> FromSwear<'rt> for f64 {
+	fn from_swear(obj: &ObjectRef<'rt>) -> Option<Self> {
+		Some(obj.access().to_count().to_f64())
+	}
+}
+
+impl<'rt> IntoSwear<'rt> for f64 {
+	fn into_swear(self) -> ObjectRef<'rt> {
+		Count::from(self).into_swear()
+	}
+}
+
+impl<'rt> FromSwear<'rt> for f32 {
+	fn from_swear(obj: &ObjectRef<'rt>) -> Option<Self> {
+		Some(obj.access().to_count().to_f64() as f32)
+	}
+}
+
+impl<'rt> IntoSwear<'rt> for f32 {
+	fn into_swear(self) -> ObjectRef<'rt> {
+		Count::from(self).into_swear()
+	}
+}
+
+macro_rules! int_swear {
+	($($int:ty),*) => {
+		$(
+			//? The whole part is kept, counts out of range don't fit rather than wrapping around.
+			//? Unsigned integers don't fit any negative count, even one whose whole part is zero.
+			impl<'rt> FromSwear<'rt> for $int {
+				fn from_swear(obj: &ObjectRef<'rt>) -> Option<Self> {
+					let count = obj.access().to_count().count;
+					if <$int>::MIN == 0 && count < crate::BigNum::ZERO {
+						return None;
+					}
+					<$int>::try_from(count.trunc().to_int().value()).ok()
+				}
+			}
+
+			impl<'rt> IntoSwear<'rt> for $int {
+				fn into_swear(self) -> ObjectRef<'rt> {
+					Count::from(self).into_swear()
+				}
+			}
+		)*
+	};
+}
+
+int_swear!(i8, u8, i16, u16, i32, u32, i64, u64, i128, u128, isize, usize);
+
+/// Anything fits, and nothing becomes Zip.
+impl<'rt> FromSwear<'rt> for () {
+	fn from_swear(_: &ObjectRef<'rt>) -> Option<Self> {
+		Some(())
+	}
+}
+
+impl<'rt> IntoSwear<'rt> for () {
+	fn into_swear(self) -> ObjectRef<'rt> {
+		Zip.into_swear()
+	}
+}
+
+/// Zip or a missing argument are None.
+impl<'rt, T: FromSwear<'rt>> FromSwear<'rt> for Option<T> {
+	fn from_swear(obj: &ObjectRef<'rt>) -> Option<Self> {
+		match obj.access().is_zip() {
+			true => Some(None),
+			false => T::from_swear(obj).map(Some),
+		}
+	}
+
+	fn missing() -> Option<Self> {
+		Some(None)
+	}
+}
+
+impl<'rt, T: IntoSwear<'rt>> IntoSwear<'rt> for Option<T> {
+	fn into_swear(self) -> ObjectRef<'rt> {
+		match self {
+			Some(value) => value.into_swear(),
+			None => ().into_swear(),
+		}
+	}
+}
+
+/// Fits if every item does.
+impl<'rt, T: FromSwear<'rt>> FromSwear<'rt> for Vec<T> {
+	fn from_swear(obj: &ObjectRef<'rt>) -> Option<Self> {
+		obj.access().to_deck().deck.iter().map(T::from_swear).collect()
+	}
+}
+
+impl<'rt, T: IntoSwear<'rt>> IntoSwear<'rt> for Vec<T> {
+	fn into_swear(self) -> ObjectRef<'rt> {
+		Deck::from_iter_ref(self.into_iter().map(T::into_swear)).into_swear()
+	}
+}
+
+/// Fits if every key and value does.
+impl<'rt, K: FromSwear<'rt> + Eq + Hash, V: FromSwear<'rt>> FromSwear<'rt> for HashMap<K, V> {
+	fn from_swear(obj: &ObjectRef<'rt>) -> Option<Self> {
+		obj.access().to_map().map.iter().map(|(k, v)| Some((K::from_swear(k)?, V::from_swear(v)?))).collect()
+	}
+}
+
+impl<'rt, K: IntoSwear<'rt>, V: IntoSwear<'rt>> IntoSwear<'rt> for HashMap<K, V> {
+	fn into_swear(self) -> ObjectRef<'rt> {
+		self.into_iter().map(|(k, v)| (k.into_swear(), v.into_swear())).collect::<Map>().into_swear()
+	}
+}
+
+impl<'rt, K: FromSwear<'rt> + Ord, V: FromSwear<'rt>> FromSwear<'rt> for BTreeMap<K, V> {
+	fn from_swear(obj: &ObjectRef<'rt>) -> Option<Self> {
+		obj.access().to_map().map.iter().map(|(k, v)| Some((K::from_swear(k)?, V::from_swear(v)?))).collect()
+	}
+}
+
+impl<'rt, K: IntoSwear<'rt>, V: IntoSwear<'rt>> IntoSwear<'rt> for BTreeMap<K, V> {
+	fn into_swear(self) -> ObjectRef<'rt> {
+		self.into_iter().map(|(k, v)| (k.into_swear(), v.into_swear())).collect::<Map>().into_swear()
+	}
+}
+
+macro_rules! tuple_swear {
+	($(($($item:ident),+)),*) => {
+		$(
+			/// A Deck with exactly one item for each element.
+			#[allow(non_snake_case)]
+			impl<'rt, $($item: FromSwear<'rt>),+> FromSwear<'rt> for ($($item,)+) {
+				fn from_swear(obj: &ObjectRef<'rt>) -> Option<Self> {
+					let deck = obj.access().to_deck();
+					let mut items = deck.deck.iter();
+					let tuple = ($($item::from_swear(items.next()?)?,)+);
+					items.next().is_none().then_some(tuple)
+				}
+			}
+
+			#[allow(non_snake_case)]
+			impl<'rt, $($item: IntoSwear<'rt>),+> IntoSwear<'rt> for ($($item,)+) {
+				fn into_swear(self) -> ObjectRef<'rt> {
+					let ($($item,)+) = self;
+					Deck::from_iter_ref([$($item.into_swear()),+]).into_swear()
+				}
+			}
+		)*
+	};
+}
+
+tuple_swear!((A), (A, B), (A, B, C), (A, B, C, D), (A, B, C, D, E), (A, B, C, D, E, F));
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	/// Asserts that `value` comes back unchanged from an Object.
+	fn round_trip<T: IntoSwear<'static> + FromSwear<'static> + Clone + PartialEq + std::fmt::Debug>(value: T) {
+		let obj = value.clone().into_swear();
+		assert_eq!(T::from_swear(&obj), Some(value), "{obj:?}");
+	}
+
+	fn from<T: FromSwear<'static>>(obj: impl IntoSwear<'static>) -> Option<T> {
+		T::from_swear(&obj.into_swear())
+	}
+
+	#[test]
+	fn test_round_trip() {
+		round_trip(true);
+		round_trip(false);
+		round_trip(0.1f64);
+		round_trip(-2.5f32);
+		round_trip(-5i64);
+		round_trip(200u8);
+		round_trip(u128::MAX);
+		round_trip(i128::MIN);
+		round_trip(String::from("héllo"));
+		round_trip(());
+	}
+
+	#[test]
+	fn test_round_trip_collections() {
+		round_trip(Some(3i32));
+		round_trip(None::<i32>);
+		round_trip(vec![1i64, 2, 3]);
+		round_trip(Vec::<String>::new());
+		round_trip(vec![vec![1u8], vec![2, 3]]);
+		round_trip(HashMap::from([(String::from("a"), 1i64), (String::from("b"), 2)]));
+		round_trip(BTreeMap::from([(1u32, String::from("one")), (2, String::from("two"))]));
+		round_trip((1i64, String::from("two"), false));
+		round_trip((Some(1.5f64),));
+	}
+
+	/// Test that integers keep the whole part of a Count, and don't fit rather than wrap when out of range.
+	#[test]
+	fn test_int_range() {
+		assert_eq!(from::<i8>(3.7), Some(3));
+		assert_eq!(from::<i8>(-3.7), Some(-3));
+		assert_eq!(from::<u8>(256), None);
+		assert_eq!(from::<u8>(-1), None);
+		assert_eq!(from::<usize>(-0.5), None);
+		assert_eq!(from::<i8>(-0.5), Some(0));
+		assert_eq!(from::<i64>(u64::MAX), None);
+		assert_eq!(from::<Vec<u8>>(vec![1, -1]), None);
+	}
+
+	/// Test that Objects are read the way Swear would convert them.
+	#[test]
+	fn test_conversion() {
+		assert_eq!(from::<f64>("12"), Some(12.0));
+		assert_eq!(from::<u32>("12.9"), Some(12));
+		assert_eq!(from::<String>(12.5), Some(String::from("12.5")));
+		assert_eq!(from::<Option<i64>>(()), Some(None));
+		assert_eq!(from::<Vec<String>>(Deck::from_iter_ref(["a".into_swear(), 1.into_swear()])), Some(vec!["a".into(), "1".into()]));
+	}
+
+	/// Test that unsigned integers reject every negative Count, and keep the whole part of fractional ones.
+	#[test]
+	fn test_unsigned() {
+		assert_eq!(from::<u8>(-1), None);
+		assert_eq!(from::<u16>(-0.5), None);
+		assert_eq!(from::<u32>(-2.5), None);
+		assert_eq!(from::<u64>(i64::MIN), None);
+		assert_eq!(from::<u128>(-0.1), None);
+		assert_eq!(from::<usize>("-3"), None);
+
+		assert_eq!(from::<u8>(2.5), Some(2));
+		assert_eq!(from::<u64>(0.9), Some(0));
+		assert_eq!(from::<usize>(0), Some(0));
+	}
+
+	/// Test that only Option and its nesting fit a missing argument.
+	#[test]
+	fn test_missing() {
+		assert_eq!(i64::missing(), None);
+		assert_eq!(String::missing(), None);
+		assert_eq!(Vec::<i64>::missing(), None);
+		assert_eq!(HashMap::<String, i64>::missing(), None);
+		assert_eq!(Option::<i64>::missing(), Some(None));
+		assert_eq!(Option::<Vec<i64>>::missing(), Some(None));
+		assert_eq!(Option::<Option<i64>>::missing(), Some(None));
+	}
+
+	/// Test that Map keys are converted like any other Object, and a Map only fits if every key does.
+	#[test]
+	fn test_map_keys() {
+		let map = |entries: Vec<(ObjectRef<'static>, ObjectRef<'static>)>| Map::from(entries).into_swear();
+
+		let numbered = map(vec![("1".into_swear(), "one".into_swear()), (2.into_swear(), "two".into_swear())]);
+		assert_eq!(from::<HashMap<u8, String>>(numbered.copy()), Some(HashMap::from([(1, "one".into()), (2, "two".into())])));
+		assert_eq!(from::<HashMap<String, String>>(numbered), Some(HashMap::from([("1".into(), "one".into()), ("2".into(), "two".into())])));
+
+		let negative = map(vec![((-1).into_swear(), 1.into_swear()), (1.into_swear(), 1.into_swear())]);
+		assert_eq!(from::<HashMap<u8, i64>>(negative.copy()), None);
+		assert_eq!(from::<HashMap<i8, i64>>(negative), Some(HashMap::from([(-1, 1), (1, 1)])));
+
+		assert_eq!(from::<HashMap<String, i64>>(Map::default()), Some(HashMap::new()));
+	}
+
+	/// Test that tuples only fit Decks with exactly as many items.
+	#[test]
+	fn test_tuple_len() {
+		assert_eq!(from::<(i64, i64)>(vec![1, 2]), Some((1, 2)));
+		assert_eq!(from::<(i64, i64)>(vec![1]), None);
+		assert_eq!(from::<(i64, i64)>(vec![1, 2, 3]), None);
+	}
+
+	/// Test that a missing argument only fits an Option.
+	#[test]
+	fn test_from_arg() {
+		let args = vec![7.into_swear()];
+
+		assert_eq!(from_arg::<i64>(&args, 0), Ok(7));
+		assert_eq!(from_arg::<Option<i64>>(&args, 0), Ok(Some(7)));
+		assert_eq!(from_arg::<Option<i64>>(&args, 1), Ok(None));
+		assert_eq!(from_arg::<i64>(&args, 1), Err(()));
+		assert_eq!(from_arg::<u8>(&[(-1).into_swear()], 0), Err(()));
+	}
+}
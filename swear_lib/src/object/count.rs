@@ -1,10 +1,61 @@
+use std::str::FromStr;
+
+use crate::BigNum;
 use super::*;
 
+/// Significant digits kept when a division doesn't come out exact.
+pub const DIV_PRECISION: usize = 34;
+
+/// An object that represents a number, of any size and with any number of decimal places.
 #[cfg_attr(feature="serde", derive(serde::Serialize, serde::Deserialize))]
-#[derive(Clone, Default, PartialEq, Eq)]
+#[derive(Clone, Default, PartialEq, Eq, PartialOrd, Ord)]
 #[swear_object]
 pub struct Count {
-	pub count: i64,
+	//? Always kept at unlimited precision, so adding and multiplying never lose digits.
+	pub count: BigNum,
+}
+
+impl Count {
+	pub fn new(count: BigNum) -> Self {
+		Self { count: count.with_precision(0).value() }
+	}
+
+	/// Parses a decimal number, such as `12`, `-0.5` or `1e3`.
+	pub fn parse(text: &str) -> Option<Self> {
+		BigNum::from_str(text.trim()).ok().map(Self::new)
+	}
+
+	pub fn is_zero(&self) -> bool {
+		self.count.repr().is_zero()
+	}
+
+	/// Divides by `rhs`, rounding to [`DIV_PRECISION`] digits if the result isn't exact.
+	/// Returns `None` when dividing by zero.
+	pub fn checked_div(&self, rhs: &Count) -> Option<Count> {
+		if rhs.is_zero() {
+			return None;
+		}
+
+		let context = dashu_float::Context::<dashu_float::round::mode::HalfAway>::new(DIV_PRECISION);
+		Some(Self::new(context.div(self.count.repr(), rhs.count.repr()).value()))
+	}
+
+	/// The whole part of this count, saturating at the bounds of `i64`.
+	pub fn to_i64(&self) -> i64 {
+		let whole = self.count.trunc().to_int().value();
+		i64::try_from(whole).unwrap_or(if self.count < BigNum::ZERO { i64::MIN } else { i64::MAX })
+	}
+
+	/// The whole part of this count, saturating at the bounds of `usize`, so anything negative is zero.
+	pub fn to_usize(&self) -> usize {
+		let whole = self.count.trunc().to_int().value();
+		usize::try_from(whole).unwrap_or(if self.count < BigNum::ZERO { 0 } else { usize::MAX })
+	}
+
+	/// The nearest `f64` to this count.
+	pub fn to_f64(&self) -> f64 {
+		self.count.to_f64().value()
+	}
 }
 
 impl<'rt> Count {
@@ -17,14 +68,14 @@ impl<'rt> Count {
 	}
 
 	fn to_swear_state(&self) -> State {
-		State { state: self.count > 1 }
+		State { state: self.count > BigNum::ONE }
 	}
 
 	fn to_swear_deck(&self) -> Deck<'rt> {
-		let mut i = self.count.clone();
+		let mut i = self.to_i64();
 		let mut deck = vec![];
 		while i > 0 {
-			deck.push(Object::from(Count::from(i.clone())).into());
+			deck.push(Object::from(Count::from(i)).into());
 			i -= 1;
 		}
 
@@ -40,7 +91,7 @@ impl<'rt> Count {
 			
 		// Add function.
 		// Adds all arguments to the count.
-		"add" => FunctionInfoBuilder::new("add".to_string()).build_native(Arc::new(Mutex::new(|obj: ObjectRef<'rt>, args: Vec<ObjectRef<'rt>>| {
+		"add" => FunctionInfoBuilder::new("add".to_string()).build_native(Arc::new(Mutex::new(|obj: ObjectRef<'rt>, args: Vec<ObjectRef<'rt>>, _| {
 			let mut count_lock = obj.lock();
 			let count = count_lock.as_count_mut().unwrap();
 
@@ -57,7 +108,7 @@ impl<'rt> Count {
 
 		// Sub function.
 		// Subtracts all arguments from the count.
-		"sub" => FunctionInfoBuilder::new("sub".to_string()).build_native(Arc::new(Mutex::new(|obj: ObjectRef<'rt>, args: Vec<ObjectRef<'rt>>| {
+		"sub" => FunctionInfoBuilder::new("sub".to_string()).build_native(Arc::new(Mutex::new(|obj: ObjectRef<'rt>, args: Vec<ObjectRef<'rt>>, _| {
 			let mut count_lock = obj.lock();
 			let count = count_lock.as_count_mut().unwrap();
 
@@ -74,7 +125,7 @@ impl<'rt> Count {
 
 		// Mul function.
 		// Multiplies by all arguments one after the other.
-		"mul" => FunctionInfoBuilder::new("mul".to_string()).build_native(Arc::new(Mutex::new(|obj: ObjectRef<'rt>, args: Vec<ObjectRef<'rt>>| {
+		"mul" => FunctionInfoBuilder::new("mul".to_string()).build_native(Arc::new(Mutex::new(|obj: ObjectRef<'rt>, args: Vec<ObjectRef<'rt>>, _| {
 			let mut count_lock = obj.lock();
 			let count = count_lock.as_count_mut().unwrap();
 
@@ -89,14 +140,15 @@ impl<'rt> Count {
 		}))),
 
 		// Div function.
-		// Multiplies by all arguments one after the other.
-		"div" => FunctionInfoBuilder::new("div".to_string()).build_native(Arc::new(Mutex::new(|obj: ObjectRef<'rt>, args: Vec<ObjectRef<'rt>>| {
+		// Divides by all arguments one after the other.
+		// Fails when dividing by zero.
+		"div" => FunctionInfoBuilder::new("div".to_string()).build_native(Arc::new(Mutex::new(|obj: ObjectRef<'rt>, args: Vec<ObjectRef<'rt>>, _| {
 			let mut count_lock = obj.lock();
 			let count = count_lock.as_count_mut().unwrap();
 
 			for arg in args {
 				let arg = arg.access();
-				count.count /= arg.to_count().count;
+				*count = count.checked_div(&arg.to_count()).ok_or(())?;
 			}
 
 			drop(count_lock);
@@ -106,7 +158,7 @@ impl<'rt> Count {
 
 		// Equals function.
 		// Returns true if all arguments are equal to the count.
-		"equals" => FunctionInfoBuilder::new("equals".to_string()).build_native(Arc::new(Mutex::new(|obj: ObjectRef<'rt>, args: Vec<ObjectRef<'rt>>| {
+		"equals" => FunctionInfoBuilder::new("equals".to_string()).build_native(Arc::new(Mutex::new(|obj: ObjectRef<'rt>, args: Vec<ObjectRef<'rt>>, _| {
 			let count_lock = obj.access();
 			let count = count_lock.as_count().unwrap();
 		
@@ -122,7 +174,7 @@ impl<'rt> Count {
 
 		// Greater function.
 		// Returns true if all arguments are less than the count.
-		"greater" => FunctionInfoBuilder::new("greater".to_string()).build_native(Arc::new(Mutex::new(|obj: ObjectRef<'rt>, args: Vec<ObjectRef<'rt>>| {
+		"greater" => FunctionInfoBuilder::new("greater".to_string()).build_native(Arc::new(Mutex::new(|obj: ObjectRef<'rt>, args: Vec<ObjectRef<'rt>>, _| {
 			let count_lock = obj.access();
 			let count = count_lock.as_count().unwrap();
 
@@ -138,7 +190,7 @@ impl<'rt> Count {
 
 		// Less function.
 		// Returns true if all arguments are greater than the count.
-		"less" => FunctionInfoBuilder::new("less".to_string()).build_native(Arc::new(Mutex::new(|obj: ObjectRef<'rt>, args: Vec<ObjectRef<'rt>>| {
+		"less" => FunctionInfoBuilder::new("less".to_string()).build_native(Arc::new(Mutex::new(|obj: ObjectRef<'rt>, args: Vec<ObjectRef<'rt>>, _| {
 			let count_lock = obj.access();
 			let count = count_lock.as_count().unwrap();
 
@@ -154,7 +206,7 @@ impl<'rt> Count {
 
 		// Greateq function.
 		// Returns true if all arguments are less than or equal to the count.
-		"greateq" => FunctionInfoBuilder::new("greateq".to_string()).build_native(Arc::new(Mutex::new(|obj: ObjectRef<'rt>, args: Vec<ObjectRef<'rt>>| {
+		"greateq" => FunctionInfoBuilder::new("greateq".to_string()).build_native(Arc::new(Mutex::new(|obj: ObjectRef<'rt>, args: Vec<ObjectRef<'rt>>, _| {
 			let count_lock = obj.access();
 			let count = count_lock.as_count().unwrap();
 
@@ -170,7 +222,7 @@ impl<'rt> Count {
 
 		// Lesseq function.
 		// Returns true if all arguments are greater than or equal to the count.
-		"lesseq" => FunctionInfoBuilder::new("lesseq".to_string()).build_native(Arc::new(Mutex::new(|obj: ObjectRef<'rt>, args: Vec<ObjectRef<'rt>>| {
+		"lesseq" => FunctionInfoBuilder::new("lesseq".to_string()).build_native(Arc::new(Mutex::new(|obj: ObjectRef<'rt>, args: Vec<ObjectRef<'rt>>, _| {
 			let count_lock = obj.access();
 			let count = count_lock.as_count().unwrap();
 
@@ -185,12 +237,38 @@ impl<'rt> Count {
 		}))),
 
 		// Round function.
-		// Rounds the count to the nearest whole number.
-		"round" => FunctionInfoBuilder::new("round".to_string()).build_native(Arc::new(Mutex::new(|obj: ObjectRef<'rt>, _| {
+		// Rounds the count to the nearest whole number, halves round away from zero.
+		"round" => FunctionInfoBuilder::new("round".to_string()).build_native(Arc::new(Mutex::new(|obj: ObjectRef<'rt>, _, _| {
+			let mut count_lock = obj.lock();
+			let count = count_lock.as_count_mut().unwrap();
+
+			count.count = count.count.round();
+
+			drop(count_lock);
+
+			Ok(Some(obj))
+		}))),
+
+		// Floor function.
+		// Rounds the count down to a whole number.
+		"floor" => FunctionInfoBuilder::new("floor".to_string()).build_native(Arc::new(Mutex::new(|obj: ObjectRef<'rt>, _, _| {
+			let mut count_lock = obj.lock();
+			let count = count_lock.as_count_mut().unwrap();
+
+			count.count = count.count.floor();
+
+			drop(count_lock);
+
+			Ok(Some(obj))
+		}))),
+
+		// Ceil function.
+		// Rounds the count up to a whole number.
+		"ceil" => FunctionInfoBuilder::new("ceil".to_string()).build_native(Arc::new(Mutex::new(|obj: ObjectRef<'rt>, _, _| {
 			let mut count_lock = obj.lock();
 			let count = count_lock.as_count_mut().unwrap();
 
-			// count.count = count.count.round();
+			count.count = count.count.ceil();
 
 			drop(count_lock);
 
@@ -199,73 +277,146 @@ impl<'rt> Count {
 
 		// Lest function.
 		// No op, returns this Object.
-		"lest" => FunctionInfoBuilder::new("lest".to_string()).build_native(Arc::new(Mutex::new(|obj, _| Ok(Some(obj))))),
+		"lest" => FunctionInfoBuilder::new("lest".to_string()).build_native(Arc::new(Mutex::new(|obj, _, _| Ok(Some(obj))))),
 
 		// Solid function.
 		// Returns false if Zip.
-		"solid" => FunctionInfoBuilder::new("solid".to_string()).build_native(Arc::new(Mutex::new(|_, _| Ok(Some(Object::from(State::from(true)).into()))))),
+		"solid" => FunctionInfoBuilder::new("solid".to_string()).build_native(Arc::new(Mutex::new(|_, _, _| Ok(Some(Object::from(State::from(true)).into()))))),
 
 			_ => return None,
 		})
 	}
 }
 
+//? The count is always normalised, so equal values share a representation.
+impl std::hash::Hash for Count {
+	fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+		self.count.repr().significand().hash(state);
+		self.count.repr().exponent().hash(state);
+	}
+}
+
 impl std::fmt::Debug for Count {
 	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-		write!(f, "Count({:?})", self.count)
+		write!(f, "Count({})", self.count)
 	}
 }
 
-impl From<i64> for Count {
-	fn from(count: i64) -> Self {
-		Self { count }
+impl From<BigNum> for Count {
+	fn from(count: BigNum) -> Self {
+		Self::new(count)
 	}
 }
 
-// impl From<f64> for Count {
-// 	fn from(count: f64) -> Self {
-// 		Self { count: count.into() }
-// 	}
-// }
-
-// impl From<f32> for Count {
-// 	fn from(count: f32) -> Self {
-// 		Self { count: count.into() }
-// 	}
-// }
-
-// impl From<i64> for Count {
-// 	fn from(count: i64) -> Self {
-// 		Self { count: count.into() }
-// 	}
-// }
-
-impl From<u64> for Count {
-	fn from(count: u64) -> Self {
-		Self { count: count as i64 }
+impl From<f64> for Count {
+	/// Uses the shortest decimal that round trips to `count`.
+	/// Non-finite values become zero.
+	fn from(count: f64) -> Self {
+		match count.is_finite() {
+			true => Self::parse(&count.to_string()).unwrap_or_default(),
+			false => Self::default(),
+		}
 	}
 }
 
-impl From<i32> for Count {
-	fn from(count: i32) -> Self {
-		Self { count: count.into() }
+impl From<f32> for Count {
+	fn from(count: f32) -> Self {
+		match count.is_finite() {
+			true => Self::parse(&count.to_string()).unwrap_or_default(),
+			false => Self::default(),
+		}
 	}
 }
 
-impl From<u32> for Count {
-	fn from(count: u32) -> Self {
-		Self { count: count.into() }
-	}
+macro_rules! count_from_int {
+	($($int:ty),*) => {
+		$(
+			impl From<$int> for Count {
+				fn from(count: $int) -> Self {
+					Self { count: BigNum::from(count) }
+				}
+			}
+		)*
+	};
 }
 
-impl From<isize> for Count {
-	fn from(count: isize) -> Self {
-		Self { count: count as i64 }
+count_from_int!(i8, u8, i16, u16, i32, u32, i64, u64, i128, u128, isize, usize);
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn count(text: &str) -> Count {
+		Count::parse(text).expect("Failed to parse count")
+	}
+
+	/// Calls a function on a Count, returning the Count it leaves behind.
+	fn call(text: &str, name: &str, args: &[&str]) -> Result<Count, ()> {
+		let obj: ObjectRef = Object::from(count(text)).into();
+		let args = args.iter().map(|arg| Object::from(count(arg)).into()).collect();
+		let result = call_native(&obj, name, args)?.expect("Expected a result");
+		let result = result.access().to_count();
+		Ok(result)
+	}
+
+	#[test]
+	fn test_parse() {
+		assert_eq!(count("12"), Count::from(12));
+		assert_eq!(count("-0.5"), Count::from(-0.5));
+		assert_eq!(count(" 1e3 "), Count::from(1000));
+		assert_eq!(Count::parse("twelve"), None);
+	}
+
+	/// Test that decimals and big numbers are kept exactly, rather than as the nearest `f64`.
+	#[test]
+	fn test_exact() {
+		assert_eq!(call("0.1", "add", &["0.2"]), Ok(count("0.3")));
+		assert_eq!(call("0.3", "sub", &["0.1", "0.2"]), Ok(count("0")));
+		assert_eq!(call("99999999999999999999", "mul", &["99999999999999999999"]), Ok(count("9999999999999999999800000000000000000001")));
+		assert_eq!(call("1", "div", &["8"]), Ok(count("0.125")));
+	}
+
+	/// Test that dividing by zero fails, and that inexact division is rounded to `DIV_PRECISION` digits.
+	#[test]
+	fn test_div() {
+		assert_eq!(call("1", "div", &["0"]), Err(()));
+		assert_eq!(call("1", "div", &["2", "-0"]), Err(()));
+
+		assert_eq!(call("1", "div", &["3"]), Ok(count(&format!("0.{}", "3".repeat(DIV_PRECISION)))));
+		assert_eq!(call("-2", "div", &["3"]), Ok(count(&format!("-0.{}7", "6".repeat(DIV_PRECISION - 1)))));
+	}
+
+	/// Test that halves round away from zero, and floor and ceil round towards their side of it.
+	#[test]
+	fn test_rounding() {
+		assert_eq!(call("2.5", "round", &[]), Ok(count("3")));
+		assert_eq!(call("-2.5", "round", &[]), Ok(count("-3")));
+		assert_eq!(call("2.49", "round", &[]), Ok(count("2")));
+		assert_eq!(call("-1.5", "floor", &[]), Ok(count("-2")));
+		assert_eq!(call("-1.5", "ceil", &[]), Ok(count("-1")));
+		assert_eq!(call("1.5", "ceil", &[]), Ok(count("2")));
+	}
+
+	/// Test that whole parts are truncated, and saturate rather than wrap when they don't fit.
+	#[test]
+	fn test_whole() {
+		assert_eq!(count("7.9").to_i64(), 7);
+		assert_eq!(count("-7.9").to_i64(), -7);
+		assert_eq!(count("1e30").to_i64(), i64::MAX);
+		assert_eq!(count("-1e30").to_i64(), i64::MIN);
+
+		assert_eq!(count("7.9").to_usize(), 7);
+		assert_eq!(count("-5").to_usize(), 0);
+		assert_eq!(count("1e30").to_usize(), usize::MAX);
 	}
-}
 
-impl From<usize> for Count {
-	fn from(count: usize) -> Self {
-		Self { count: count as i64 }
+	/// Test that floats convert through their shortest decimal, and anything not finite is zero.
+	#[test]
+	fn test_from_float() {
+		assert_eq!(Count::from(0.1), count("0.1"));
+		assert_eq!(Count::from(0.1f32), count("0.1"));
+		assert_eq!(Count::from(f64::NAN), Count::default());
+		assert_eq!(Count::from(f64::INFINITY), Count::default());
+		assert_eq!(count("0.1").to_f64(), 0.1);
 	}
 }
@@ -1,4 +1,4 @@
-use crate::runtime::ObjectRef;
+use crate::runtime::{Invoker, NativeResult, ObjectRef};
 use super::*;
 
 #[cfg_attr(feature="serde", derive(serde::Serialize, serde::Deserialize))]
@@ -24,6 +24,26 @@ impl<'rt> Deck<'rt> {
 	pub fn from_iter_lit<I: IntoIterator<Item = ObjectLiteral>>(iter: I) -> Self {
 		Self { deck: iter.into_iter().map(|l| Object::from_literal(&l).into()).collect() }
 	}
+
+	/// Turns a Count into an index of this deck, negative counts index from the end.
+	/// Returns None if it's out of range.
+	pub fn index(&self, index: &Count) -> Option<usize> {
+		let len = self.deck.len() as i64;
+		let index = index.to_i64();
+		let index = if index < 0 { len + index } else { index };
+
+		(0..len).contains(&index).then_some(index as usize)
+	}
+
+	/// Like [`Deck::index`], but clamps out of range counts to the ends of the deck.
+	fn clamp(&self, index: &Count) -> usize {
+		let len = self.deck.len() as i64;
+		let index = index.to_i64();
+		let index = if index < 0 { len + index } else { index };
+
+		index.clamp(0, len) as usize
+	}
+
 }
 
 impl<'rt> Deck<'rt> {
@@ -62,12 +82,224 @@ impl<'rt> Deck<'rt> {
 			// Lest function.
 			// No op, returns this Object.
 			"lest" =>
-				FunctionInfoBuilder::new("lest".to_string()).build_native(Arc::new(Mutex::new(|obj, _| Ok(Some(obj)) ))),
+				FunctionInfoBuilder::new("lest".to_string()).build_native(Arc::new(Mutex::new(|obj, _, _| Ok(Some(obj)) ))),
 			
 			// Solid function.
 			// Returns false if Zip.
 			"solid" =>
-				FunctionInfoBuilder::new("solid".to_string()).build_native(Arc::new(Mutex::new(|_, _| Ok(Some(Object::from(State::from(true)).into())) ))),
+				FunctionInfoBuilder::new("solid".to_string()).build_native(Arc::new(Mutex::new(|_, _, _| Ok(Some(Object::from(State::from(true)).into())) ))),
+
+			// Get function.
+			// Returns the item at the given index, or Zip if there isn't one.
+			"get" => FunctionInfoBuilder::new("get".to_string()).build_native(Arc::new(Mutex::new(|obj: ObjectRef<'rt>, args: Vec<ObjectRef<'rt>>, _| {
+				let index = args.first().ok_or(())?.access().to_count();
+
+				let deck_lock = obj.access();
+				let deck = deck_lock.as_deck().unwrap();
+
+				Ok(Some(match deck.index(&index) {
+					Some(i) => deck.deck[i].copy(),
+					None => Object::from(Zip).into(),
+				}))
+			}))),
+
+			// Set function.
+			// Replaces the item at the given index.
+			// Fails if the index is out of range.
+			"set" => FunctionInfoBuilder::new("set".to_string()).build_native(Arc::new(Mutex::new(|obj: ObjectRef<'rt>, args: Vec<ObjectRef<'rt>>, _| {
+				let [index, value] = <[_; 2]>::try_from(args).map_err(|_| ())?;
+				let index = index.access().to_count();
+
+				let mut deck_lock = obj.lock();
+				let deck = deck_lock.as_deck_mut().unwrap();
+
+				let i = deck.index(&index).ok_or(())?;
+				deck.deck[i] = value;
+
+				drop(deck_lock);
+
+				Ok(Some(obj))
+			}))),
+
+			// Push function.
+			// Adds all arguments to the end of the deck.
+			"push" => FunctionInfoBuilder::new("push".to_string()).build_native(Arc::new(Mutex::new(|obj: ObjectRef<'rt>, args: Vec<ObjectRef<'rt>>, _| {
+				let mut deck_lock = obj.lock();
+				let deck = deck_lock.as_deck_mut().unwrap();
+
+				deck.deck.extend(args);
+
+				drop(deck_lock);
+
+				Ok(Some(obj))
+			}))),
+
+			// Pop function.
+			// Removes and returns the last item, or Zip if the deck is empty.
+			"pop" => FunctionInfoBuilder::new("pop".to_string()).build_native(Arc::new(Mutex::new(|obj: ObjectRef<'rt>, _, _| {
+				let mut deck_lock = obj.lock();
+				let deck = deck_lock.as_deck_mut().unwrap();
+
+				Ok(Some(deck.deck.pop().unwrap_or_else(|| Object::from(Zip).into())))
+			}))),
+
+			// Insert function.
+			// Inserts an item before the given index, an index equal to the length appends.
+			// Fails if the index is out of range.
+			"insert" => FunctionInfoBuilder::new("insert".to_string()).build_native(Arc::new(Mutex::new(|obj: ObjectRef<'rt>, args: Vec<ObjectRef<'rt>>, _| {
+				let [index, value] = <[_; 2]>::try_from(args).map_err(|_| ())?;
+				let index = index.access().to_count();
+
+				let mut deck_lock = obj.lock();
+				let deck = deck_lock.as_deck_mut().unwrap();
+
+				let i = match deck.index(&index) {
+					Some(i) => i,
+					None if index.to_i64() == deck.deck.len() as i64 => deck.deck.len(),
+					None => return Err(()),
+				};
+				deck.deck.insert(i, value);
+
+				drop(deck_lock);
+
+				Ok(Some(obj))
+			}))),
+
+			// Remove function.
+			// Removes and returns the item at the given index.
+			// Fails if the index is out of range.
+			"remove" => FunctionInfoBuilder::new("remove".to_string()).build_native(Arc::new(Mutex::new(|obj: ObjectRef<'rt>, args: Vec<ObjectRef<'rt>>, _| {
+				let index = args.first().ok_or(())?.access().to_count();
+
+				let mut deck_lock = obj.lock();
+				let deck = deck_lock.as_deck_mut().unwrap();
+
+				let i = deck.index(&index).ok_or(())?;
+				Ok(Some(deck.deck.remove(i)))
+			}))),
+
+			// Slice function.
+			// Returns a new deck with the items from the first index up to, but not including, the second.
+			// Without a second index the slice runs to the end. Out of range indices are clamped.
+			"slice" => FunctionInfoBuilder::new("slice".to_string()).build_native(Arc::new(Mutex::new(|obj: ObjectRef<'rt>, args: Vec<ObjectRef<'rt>>, _| {
+				let bounds: Vec<Count> = args.iter().map(|arg| arg.access().to_count()).collect();
+
+				let deck_lock = obj.access();
+				let deck = deck_lock.as_deck().unwrap();
+
+				let len = deck.deck.len();
+				let start = bounds.first().map(|b| deck.clamp(b)).unwrap_or(0);
+				let end = bounds.get(1).map(|b| deck.clamp(b)).unwrap_or(len).max(start);
+
+				Ok(Some(Object::from(Deck::from_iter_ref(deck.deck[start..end].iter().cloned())).into()))
+			}))),
+
+			// Reverse function.
+			// Reverses the order of the items.
+			"reverse" => FunctionInfoBuilder::new("reverse".to_string()).build_native(Arc::new(Mutex::new(|obj: ObjectRef<'rt>, _, _| {
+				let mut deck_lock = obj.lock();
+				let deck = deck_lock.as_deck_mut().unwrap();
+
+				deck.deck.reverse();
+
+				drop(deck_lock);
+
+				Ok(Some(obj))
+			}))),
+
+			// Contains function.
+			// Returns true if any item equals the argument.
+			// Dynamics are compared with their `equals` callback if they define one.
+			"contains" => FunctionInfoBuilder::new("contains".to_string()).build_native(Arc::new(Mutex::new(|obj: ObjectRef<'rt>, args: Vec<ObjectRef<'rt>>, invoker: Invoker<'rt>| {
+				let needle = args.first().ok_or(())?.copy();
+				let items = obj.access().to_deck().deck;
+
+				find_from(items, needle, 0, invoker, |found, _| Ok(Some(Object::from(State::from(found.is_some())).into())))
+			}))),
+
+			// Find function.
+			// Returns the index of the first item equal to the argument, or Zip if there isn't one.
+			// Dynamics are compared with their `equals` callback if they define one.
+			"find" => FunctionInfoBuilder::new("find".to_string()).build_native(Arc::new(Mutex::new(|obj: ObjectRef<'rt>, args: Vec<ObjectRef<'rt>>, invoker: Invoker<'rt>| {
+				let needle = args.first().ok_or(())?.copy();
+				let items = obj.access().to_deck().deck;
+
+				find_from(items, needle, 0, invoker, |found, _| Ok(Some(match found {
+					Some(i) => Object::from(Count::from(i)).into(),
+					None => Object::from(Zip).into(),
+				})))
+			}))),
+
+			// Sort function.
+			// Sorts the items in ascending order.
+			// Items of different kinds are grouped: Zip, State, Count, Chars, Deck, Map, Dynamic then Callable.
+			"sort" => FunctionInfoBuilder::new("sort".to_string()).build_native(Arc::new(Mutex::new(|obj: ObjectRef<'rt>, _, _| {
+				let mut deck_lock = obj.lock();
+				let deck = deck_lock.as_deck_mut().unwrap();
+
+				deck.deck.sort();
+
+				drop(deck_lock);
+
+				Ok(Some(obj))
+			}))),
+
+			// Join function.
+			// Returns the items as Chars, separated by the argument if there is one.
+			"join" => FunctionInfoBuilder::new("join".to_string()).build_native(Arc::new(Mutex::new(|obj: ObjectRef<'rt>, args: Vec<ObjectRef<'rt>>, _| {
+				let separator = args.first().map(|arg| arg.access().to_chars().chars).unwrap_or_default();
+
+				let deck_lock = obj.access();
+				let deck = deck_lock.as_deck().unwrap();
+
+				let chars = deck.deck.iter()
+					.map(|o| o.access().to_chars().chars)
+					.collect::<Vec<_>>()
+					.join(&separator);
+
+				Ok(Some(Object::from(Chars::from(chars)).into()))
+			}))),
+
+			// Map function.
+			// Returns a new deck with the result of calling the argument on each item.
+			"map" => FunctionInfoBuilder::new("map".to_string()).build_native(Arc::new(Mutex::new(|obj: ObjectRef<'rt>, args: Vec<ObjectRef<'rt>>, invoker: Invoker<'rt>| {
+				let callback = args.first().ok_or(())?.copy();
+				let items = obj.access().to_deck().deck;
+
+				map_from(items, Vec::new(), callback, invoker)
+			}))),
+
+			// Filter function.
+			// Returns a new deck with the items the argument returns true for.
+			"filter" => FunctionInfoBuilder::new("filter".to_string()).build_native(Arc::new(Mutex::new(|obj: ObjectRef<'rt>, args: Vec<ObjectRef<'rt>>, invoker: Invoker<'rt>| {
+				let callback = args.first().ok_or(())?.copy();
+				let items = obj.access().to_deck().deck;
+
+				filter_from(items, Vec::new(), callback, invoker)
+			}))),
+
+			// Reduce function.
+			// Calls the first argument with the running total and each item, starting from the second argument.
+			// Without a starting value, the first item is used.
+			"reduce" => FunctionInfoBuilder::new("reduce".to_string()).build_native(Arc::new(Mutex::new(|obj: ObjectRef<'rt>, args: Vec<ObjectRef<'rt>>, invoker: Invoker<'rt>| {
+				let mut args = args.into_iter();
+				let callback = args.next().ok_or(())?;
+				let mut items = obj.access().to_deck().deck.into_iter();
+
+				let Some(total) = args.next().or_else(|| items.next()) else {
+					return Ok(Some(Object::from(Zip).into()));
+				};
+				reduce_from(items, total, callback, invoker)
+			}))),
+
+			// Sort by function.
+			// Sorts the items in place, the argument is called with two items and returns true if the first goes before the second.
+			"sort_by" => FunctionInfoBuilder::new("sort_by".to_string()).build_native(Arc::new(Mutex::new(|obj: ObjectRef<'rt>, args: Vec<ObjectRef<'rt>>, invoker: Invoker<'rt>| {
+				let callback = args.first().ok_or(())?.copy();
+				let items = obj.access().to_deck().deck;
+
+				sort_from(obj, items.into_iter(), Vec::new(), callback, invoker)
+			}))),
 
 			_ => return None,
 		})
@@ -75,6 +307,105 @@ impl<'rt> Deck<'rt> {
 	}
 }
 
+//? These natives call back into Swear one item at a time, each step continues from the last.
+
+fn map_from<'rt>(mut items: Vec<ObjectRef<'rt>>, mut done: Vec<ObjectRef<'rt>>, callback: ObjectRef<'rt>, invoker: Invoker<'rt>) -> NativeResult<'rt> {
+	if done.len() == items.len() {
+		return Ok(Some(Object::from(Deck::from(done)).into()));
+	}
+
+	let item = std::mem::take(&mut items[done.len()]);
+	invoker.invoke(callback.copy(), vec![item], move |result, invoker| {
+		done.push(result);
+		map_from(items, done, callback, invoker)
+	});
+	Ok(None)
+}
+
+fn filter_from<'rt>(mut items: Vec<ObjectRef<'rt>>, mut kept: Vec<ObjectRef<'rt>>, callback: ObjectRef<'rt>, invoker: Invoker<'rt>) -> NativeResult<'rt> {
+	//? Items are taken from the back, so `kept` is built in reverse.
+	let Some(item) = items.pop() else {
+		kept.reverse();
+		return Ok(Some(Object::from(Deck::from(kept)).into()));
+	};
+
+	invoker.invoke(callback.copy(), vec![item.copy()], move |result, invoker| {
+		if result.access().to_state().state {
+			kept.push(item);
+		}
+		filter_from(items, kept, callback, invoker)
+	});
+	Ok(None)
+}
+
+fn reduce_from<'rt>(mut items: std::vec::IntoIter<ObjectRef<'rt>>, total: ObjectRef<'rt>, callback: ObjectRef<'rt>, invoker: Invoker<'rt>) -> NativeResult<'rt> {
+	let Some(item) = items.next() else {
+		return Ok(Some(total));
+	};
+
+	invoker.invoke(callback.copy(), vec![total, item], move |total, invoker| {
+		reduce_from(items, total, callback, invoker)
+	});
+	Ok(None)
+}
+
+/// Finds the first item from `start` on equal to `needle`, calling `equals` callbacks where there are any.
+/// Items that don't need a call are compared straight away, rather than one per step.
+fn find_from<'rt>(
+	items: Vec<ObjectRef<'rt>>,
+	needle: ObjectRef<'rt>,
+	start: usize,
+	invoker: Invoker<'rt>,
+	found: impl FnOnce(Option<usize>, Invoker<'rt>) -> NativeResult<'rt> + 'rt,
+) -> NativeResult<'rt> {
+	for (index, item) in items.iter().enumerate().skip(start) {
+		if *item == needle {
+			return found(Some(index), invoker);
+		}
+
+		if let Some((target, equals, other)) = equals_hook(&needle, item) {
+			invoker.invoke_on(target, equals, vec![other], move |equal, invoker| {
+				match equal.access().to_state().state {
+					true => found(Some(index), invoker),
+					false => find_from(items, needle, index + 1, invoker, found),
+				}
+			});
+			return Ok(None);
+		}
+	}
+
+	found(None, invoker)
+}
+
+/// A binary insertion sort, one comparison per call.
+fn sort_from<'rt>(obj: ObjectRef<'rt>, mut items: std::vec::IntoIter<ObjectRef<'rt>>, sorted: Vec<ObjectRef<'rt>>, callback: ObjectRef<'rt>, invoker: Invoker<'rt>) -> NativeResult<'rt> {
+	let Some(item) = items.next() else {
+		obj.lock().as_deck_mut().ok_or(())?.deck = sorted;
+		return Ok(Some(obj));
+	};
+
+	let len = sorted.len();
+	insert_from(obj, items, sorted, item, 0..len, callback, invoker)
+}
+
+/// Finds where `item` goes within `range` of the sorted items.
+fn insert_from<'rt>(obj: ObjectRef<'rt>, items: std::vec::IntoIter<ObjectRef<'rt>>, mut sorted: Vec<ObjectRef<'rt>>, item: ObjectRef<'rt>, range: std::ops::Range<usize>, callback: ObjectRef<'rt>, invoker: Invoker<'rt>) -> NativeResult<'rt> {
+	if range.is_empty() {
+		sorted.insert(range.start, item);
+		return sort_from(obj, items, sorted, callback, invoker);
+	}
+
+	let mid = (range.start + range.end) / 2;
+	invoker.invoke(callback.copy(), vec![item.copy(), sorted[mid].copy()], move |before, invoker| {
+		let range = match before.access().to_state().state {
+			true => range.start..mid,
+			false => mid + 1..range.end,
+		};
+		insert_from(obj, items, sorted, item, range, callback, invoker)
+	});
+	Ok(None)
+}
+
 impl<'rt> std::fmt::Debug for Deck<'rt> {
 	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
 		let mut debug = f.debug_list();
@@ -96,3 +427,157 @@ impl<'rt> From<Vec<Object<'rt>>> for Deck<'rt> {
 		Self { deck: deck.into_iter().map(ObjectRef::from).collect() }
 	}
 }
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn deck(items: &[i64]) -> ObjectRef<'static> {
+		Object::from(Deck::from_iter_obj(items.iter().map(|item| Object::from(Count::from(*item))))).into()
+	}
+
+	fn count(count: i64) -> ObjectRef<'static> {
+		Object::from(Count::from(count)).into()
+	}
+
+	/// The items of a Deck of Counts.
+	fn items(obj: &ObjectRef) -> Vec<i64> {
+		obj.access().to_deck().deck.iter().map(|item| item.access().to_count().to_i64()).collect()
+	}
+
+	fn call(obj: &ObjectRef<'static>, name: &str, args: Vec<ObjectRef<'static>>) -> Result<ObjectRef<'static>, ()> {
+		Ok(call_native(obj, name, args)?.expect("Expected a result"))
+	}
+
+	/// Runs a script to the end, returning what it left on the table.
+	#[cfg(feature="parser")]
+	fn run(script: &str) -> ObjectRef<'static> {
+		use crate::runtime::{ContextStack, SwearRuntime};
+
+		let script = swear_parser::SwearParser::new().parse(script).expect("Failed to parse input");
+		let mut runtime = ContextStack::new(Box::leak(Box::new(script)));
+		while !runtime.is_finished() {
+			runtime.step().expect("Failed to run script");
+		}
+		runtime.take_result().unwrap_or_default().into()
+	}
+
+	/// Test that negative indices count from the end, and anything past either end is out of range.
+	#[test]
+	fn test_index() {
+		let deck = Deck::from(vec![Object::from(Zip); 3]);
+
+		assert_eq!(deck.index(&Count::from(0)), Some(0));
+		assert_eq!(deck.index(&Count::from(2)), Some(2));
+		assert_eq!(deck.index(&Count::from(-1)), Some(2));
+		assert_eq!(deck.index(&Count::from(-3)), Some(0));
+		assert_eq!(deck.index(&Count::from(3)), None);
+		assert_eq!(deck.index(&Count::from(-4)), None);
+		assert_eq!(Deck::default().index(&Count::from(0)), None);
+	}
+
+	#[test]
+	fn test_get_set() {
+		let obj = deck(&[1, 2, 3]);
+
+		assert_eq!(call(&obj, "get", vec![count(-1)]).map(|item| item.access().to_count()), Ok(Count::from(3)));
+		assert!(call(&obj, "get", vec![count(3)]).expect("Failed to get").access().is_zip());
+
+		call(&obj, "set", vec![count(-3), count(9)]).expect("Failed to set");
+		assert_eq!(items(&obj), [9, 2, 3]);
+		assert_eq!(call(&obj, "set", vec![count(3), count(9)]).err(), Some(()));
+		assert_eq!(call(&obj, "set", vec![count(0)]).err(), Some(()));
+	}
+
+	/// Test that inserting at the length appends, and any further out fails.
+	#[test]
+	fn test_insert() {
+		let obj = deck(&[1, 2]);
+
+		call(&obj, "insert", vec![count(2), count(3)]).expect("Failed to append");
+		call(&obj, "insert", vec![count(0), count(0)]).expect("Failed to insert at the start");
+		call(&obj, "insert", vec![count(-1), count(9)]).expect("Failed to insert from the end");
+		assert_eq!(items(&obj), [0, 1, 2, 9, 3]);
+
+		assert_eq!(call(&obj, "insert", vec![count(6), count(4)]).err(), Some(()));
+		assert_eq!(call(&obj, "insert", vec![count(-6), count(4)]).err(), Some(()));
+		assert_eq!(items(&obj), [0, 1, 2, 9, 3]);
+
+		let empty = deck(&[]);
+		call(&empty, "insert", vec![count(0), count(1)]).expect("Failed to insert into an empty deck");
+		assert_eq!(items(&empty), [1]);
+	}
+
+	#[test]
+	fn test_remove() {
+		let obj = deck(&[1, 2, 3]);
+
+		assert_eq!(call(&obj, "remove", vec![count(-1)]).map(|item| item.access().to_count()), Ok(Count::from(3)));
+		assert_eq!(call(&obj, "remove", vec![count(2)]).err(), Some(()));
+		assert_eq!(call(&obj, "remove", vec![]).err(), Some(()));
+		assert_eq!(items(&obj), [1, 2]);
+
+		assert_eq!(call(&deck(&[]), "remove", vec![count(0)]).err(), Some(()));
+		assert!(call(&deck(&[]), "pop", vec![]).expect("Failed to pop").access().is_zip());
+	}
+
+	/// Test that slices clamp their bounds, and come out empty rather than failing when they cross.
+	#[test]
+	fn test_slice() {
+		let obj = deck(&[1, 2, 3, 4]);
+		let slice = |bounds: &[i64]| items(&call(&obj, "slice", bounds.iter().copied().map(count).collect()).expect("Failed to slice"));
+
+		assert_eq!(slice(&[1, 3]), [2, 3]);
+		assert_eq!(slice(&[2]), [3, 4]);
+		assert_eq!(slice(&[]), [1, 2, 3, 4]);
+		assert_eq!(slice(&[-2, -1]), [3]);
+		assert_eq!(slice(&[-10, 10]), [1, 2, 3, 4]);
+		assert_eq!(slice(&[3, 1]), [] as [i64; 0]);
+		assert_eq!(slice(&[10]), [] as [i64; 0]);
+
+		//? A slice is a new Deck, the original is left alone.
+		assert_eq!(items(&obj), [1, 2, 3, 4]);
+	}
+
+	/// Test that `map` and `filter` call a Swear callback on every item, in order.
+	#[cfg(feature="parser")]
+	#[test]
+	fn test_map_filter() {
+		const CALLBACKS: &str = "double! n* [n#>mul '2'#*<]\nbig! n* [n#>greater '1'#*<]\n";
+
+		assert_eq!(items(&run(&format!("{CALLBACKS}'1 2 3'|>map !double*<"))), [2, 4, 6]);
+		assert_eq!(items(&run(&format!("{CALLBACKS}''|>map !double*<"))), [] as [i64; 0]);
+
+		assert_eq!(items(&run(&format!("{CALLBACKS}'1 2 3 0 5'|>filter !big*<"))), [2, 3, 5]);
+		assert_eq!(items(&run(&format!("{CALLBACKS}''|>filter !big*<"))), [] as [i64; 0]);
+	}
+
+	/// Test that `reduce` starts from the value it's given, or the first item without one.
+	#[cfg(feature="parser")]
+	#[test]
+	fn test_reduce() {
+		const SUM: &str = "sum! total* n* [total#>add n#*<]\n";
+
+		assert_eq!(run(&format!("{SUM}'1 2 3'|>reduce !sum* '10'#*<")).access().to_count(), Count::from(16));
+		assert_eq!(run(&format!("{SUM}'1 2 3'|>reduce !sum*<")).access().to_count(), Count::from(6));
+		assert_eq!(run(&format!("{SUM}'4'|>reduce !sum*<")).access().to_count(), Count::from(4));
+
+		assert_eq!(run(&format!("{SUM}''|>reduce !sum* '10'#*<")).access().to_count(), Count::from(10));
+		assert!(run(&format!("{SUM}''|>reduce !sum*<")).access().is_zip());
+	}
+
+	/// Test that `sort_by` sorts in place, and reads whatever the comparator returns as a State.
+	#[cfg(feature="parser")]
+	#[test]
+	fn test_sort_by() {
+		const COMPARATORS: &str = "before! a* b* [a#>greater b#*<]\nalways! a* b* ['yes'$]\nnever! a* b* []\n";
+
+		assert_eq!(items(&run(&format!("{COMPARATORS}d% '3 1 4 1 5 2'|\nd>sort_by !before*<\nd"))), [5, 4, 3, 2, 1, 1]);
+		assert_eq!(items(&run(&format!("{COMPARATORS}''|>sort_by !before*<"))), [] as [i64; 0]);
+
+		//? Non-empty Chars are true, so every item goes before the ones already sorted.
+		assert_eq!(items(&run(&format!("{COMPARATORS}'1 2 3'|>sort_by !always*<"))), [3, 2, 1]);
+		//? Zip is false, so every item goes after them.
+		assert_eq!(items(&run(&format!("{COMPARATORS}'1 2 3'|>sort_by !never*<"))), [1, 2, 3]);
+	}
+}
@@ -1,4 +1,4 @@
-use crate::context::{self, ContextItem};
+use crate::context::{self, ContextItem, SwearCallback};
 use super::*;
 
 #[derive(Default, Clone)]
@@ -12,6 +12,8 @@ pub struct Dynamic<'rt> {
 	//? interacts with remains loaded until the Object no longer exists.
 	#[allow(unused)]
 	pub(crate) src_lib: Option<Arc<libloading::Library>>,
+	/// Whether this holds the inherited contents of a blueprint, as `super`.
+	prototype: bool,
 }
 
 impl<'rt> IntoIterator for Dynamic<'rt> {
@@ -23,9 +25,44 @@ impl<'rt> IntoIterator for Dynamic<'rt> {
 }
 
 impl<'rt> Dynamic<'rt> {
+	/// An empty Dynamic, reporting itself under `name`.
+	pub fn named(name: impl Into<String>) -> Self {
+		Self {
+			name: name.into(),
+			..Self::default()
+		}
+	}
+
+	/// A Dynamic to hold the contents a blueprint inherited.
+	/// Methods called on it run on whichever instance is calling them.
+	pub fn prototype() -> Self {
+		Self {
+			prototype: true,
+			..Self::default()
+		}
+	}
+
 	pub fn add_function(&mut self, info: FunctionInfo<'rt>) {
 		self.contents.insert(info.name, ContextItem::Callback(info.function));
 	}
+
+	/// A Swear callback this Object defines, for the runtime to call in place of its own behaviour.
+	pub fn hook(&self, name: &str) -> Option<SwearCallback<'rt>> {
+		match self.contents.get(name) {
+			Some(ContextItem::Callback(Callback::Swear(callback))) => Some(callback.clone()),
+			_ => None,
+		}
+	}
+}
+
+/// Finds an `equals` callback to compare `a` and `b` with, preferring one defined by `a`.
+/// Returns the Object that defines it, the callback as a Callable, and the other Object to pass to it.
+pub(crate) fn equals_hook<'rt>(a: &ObjectRef<'rt>, b: &ObjectRef<'rt>) -> Option<(ObjectRef<'rt>, ObjectRef<'rt>, ObjectRef<'rt>)> {
+	[(a, b), (b, a)].into_iter().find_map(|(target, other)| {
+		let callback = target.access().as_dynamic()?.hook("equals")?;
+		let callable = Object::from(Callable::new("equals".to_string(), Callback::Swear(callback))).into();
+		Some((target.copy(), callable, other.copy()))
+	})
 }
 
 impl<'rt> IObject<'rt> for Dynamic<'rt> {
@@ -53,6 +90,10 @@ impl<'rt> IObject<'rt> for Dynamic<'rt> {
 		true
 	}
 
+	fn is_prototype(&self) -> bool {
+		self.prototype
+	}
+
 	fn get_info(&self) -> ObjectInfo {
 		ObjectInfo::from_str(&self.name)
 			.with_description(self.description.clone())
@@ -61,10 +102,52 @@ impl<'rt> IObject<'rt> for Dynamic<'rt> {
 	fn get_function(&self, name: &str) -> Option<FunctionInfo<'rt>> {
 		// See if a function of the matching name exists in the context.
 		if let Some(ContextItem::Callback(cb)) = self.contents.get(name) {
-			Some(FunctionInfoBuilder::new(name.to_string()).build(cb.clone()))
-		} else {
-			None
+			return Some(FunctionInfoBuilder::new(name.to_string()).build(cb.clone()));
 		}
+
+		//? Methods defined by the Object take priority over these.
+		Some(match name {
+			// Get function.
+			// Returns the field with the given name, or Zip if there isn't one.
+			"get" => FunctionInfoBuilder::new("get".to_string()).build_native(Arc::new(Mutex::new(|obj: ObjectRef<'rt>, args: Vec<ObjectRef<'rt>>, _| {
+				let key = args.first().ok_or(())?.access().to_chars().chars;
+
+				let dynamic_lock = obj.access();
+				let dynamic = dynamic_lock.as_dynamic().unwrap();
+
+				Ok(Some(match dynamic.contents.get(&key) {
+					Some(ContextItem::Object(field)) => field.copy(),
+					Some(ContextItem::Callback(callback)) => Object::from(Callable::new(key, callback.clone())).into(),
+					_ => Object::from(Zip).into(),
+				}))
+			}))),
+
+			// Set function.
+			// Sets the field with the given name, from inside or outside the Object's methods.
+			"set" => FunctionInfoBuilder::new("set".to_string()).build_native(Arc::new(Mutex::new(|obj: ObjectRef<'rt>, args: Vec<ObjectRef<'rt>>, _| {
+				let [key, value] = <[_; 2]>::try_from(args).map_err(|_| ())?;
+				let key = key.access().to_chars().chars;
+
+				let mut dynamic_lock = obj.lock();
+				let dynamic = dynamic_lock.as_dynamic_mut().unwrap();
+
+				dynamic.contents.insert(key, ContextItem::Object(value));
+
+				drop(dynamic_lock);
+
+				Ok(Some(obj))
+			}))),
+
+			// Equals function.
+			// Returns true if all arguments are this very Object, rather than ones with the same contents.
+			"equals" => FunctionInfoBuilder::new("equals".to_string()).build_native(Arc::new(Mutex::new(|obj: ObjectRef<'rt>, args: Vec<ObjectRef<'rt>>, _| {
+				let all = args.iter().all(|arg| arg.ptr_eq(&obj));
+
+				Ok(Some(Object::from(State::from(all)).into()))
+			}))),
+
+			_ => return None,
+		})
 	}
 
 	// fn get_functions(&self) -> HashMap<String, FunctionInfo<'rt>> {
@@ -115,3 +198,42 @@ mod serde_impl {
 		}
 	}
 }
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn field(obj: &ObjectRef<'static>, key: &str) -> ObjectRef<'static> {
+		call_native(obj, "get", vec![Chars::from(key).into_swear()]).expect("Failed to get").expect("Expected a field")
+	}
+
+	/// Test that fields set from outside can be read back, and missing ones are Zip.
+	#[test]
+	fn test_get_set() {
+		let obj: ObjectRef = Object::from(Dynamic::named("Point")).into();
+
+		let set = call_native(&obj, "set", vec![Chars::from("x").into_swear(), Count::from(3).into_swear()]);
+		assert!(set.is_ok_and(|result| result.is_some_and(|result| result.ptr_eq(&obj))));
+		assert_eq!(field(&obj, "x").access().to_count(), Count::from(3));
+		assert!(field(&obj, "y").access().is_zip());
+
+		assert_eq!(call_native(&obj, "set", vec![Chars::from("x").into_swear()]).err(), Some(()));
+	}
+
+	/// Test that a method comes back from `get` as a Callable, and that methods win over the built in functions.
+	#[test]
+	fn test_methods() {
+		let mut dynamic = Dynamic::named("Point");
+		dynamic.add_function(FunctionInfoBuilder::new("get".to_string()).build_native(Arc::new(Mutex::new(|_, _, _| Ok(Some(Count::from(7).into_swear()))))));
+		let obj: ObjectRef = Object::from(dynamic).into();
+
+		assert_eq!(field(&obj, "anything").access().to_count(), Count::from(7));
+
+		let mut dynamic = Dynamic::named("Point");
+		dynamic.add_function(FunctionInfoBuilder::new("sum".to_string()).build_native(Arc::new(Mutex::new(|_, _, _| Ok(None)))));
+		let obj: ObjectRef = Object::from(dynamic).into();
+
+		assert!(field(&obj, "sum").access().as_callable().is_some_and(|callable| callable.name == "sum"));
+	}
+
+}
@@ -5,7 +5,40 @@ use super::*;
 #[derive(Clone, Default, PartialEq, Eq)]
 #[swear_object]
 pub struct Map<'rt> {
-	pub map: Vec<(ObjectRef<'rt>, ObjectRef<'rt>)>,
+	//? Keys are deep copies, so nothing outside the map refers to any part of one and it can't be unsorted.
+	//? Dynamics are the exception, they're keyed by identity wherever they are, and changing them doesn't move them.
+	pub map: HashMap<ObjectRef<'rt>, ObjectRef<'rt>>,
+}
+
+/// A reference to `obj` fit to be used as a key.
+fn key<'rt>(obj: &ObjectRef<'rt>) -> ObjectRef<'rt> {
+	deep_copy(obj, &mut Vec::new())
+}
+
+/// A copy of `obj` sharing nothing with it but Dynamics.
+/// `copies` pairs the Objects already copied with their copies, so an Object containing itself is copied once.
+fn deep_copy<'rt>(obj: &ObjectRef<'rt>, copies: &mut Vec<(ObjectRef<'rt>, ObjectRef<'rt>)>) -> ObjectRef<'rt> {
+	if let Some((_, copy)) = copies.iter().find(|(original, _)| original.ptr_eq(obj)) {
+		return copy.copy();
+	}
+
+	let object = obj.access().clone();
+	if object.is_dynamic() {
+		return obj.copy();
+	}
+
+	let copy = ObjectRef::new(Object::from(Zip));
+	copies.push((obj.copy(), copy.copy()));
+
+	*copy.lock() = match object {
+		Object::Deck(deck) => Object::from(Deck::from_iter_ref(deck.deck.iter().map(|item| deep_copy(item, copies)))),
+		Object::Map(map) => Object::from(Map {
+			map: map.map.iter().map(|(k, v)| (deep_copy(k, copies), deep_copy(v, copies))).collect(),
+		}),
+		object => object,
+	};
+
+	copy
 }
 
 impl<'rt> Map<'rt> {
@@ -13,6 +46,11 @@ impl<'rt> Map<'rt> {
 		Self { map: v.iter().map(|(k, v)| (Object::from_literal(k).into(), Object::from_literal(v).into())).collect() }
 	}
 
+	/// Each entry as a Deck of its key and value.
+	fn entries(&self) -> Deck<'rt> {
+		Deck::from_iter_obj(self.map.iter().map(|(k, v)| Deck::from(vec![key(k), v.copy()]).into()))
+	}
+
 	// pub fn from_iter_ref<I: IntoIterator<Item = (ObjectRef, ObjectRef)>>(iter: I) -> Self {
 	// 	Self { map: iter.into_iter().collect() }
 	// }
@@ -50,7 +88,7 @@ impl<'rt> Map<'rt> {
 	}
 
 	fn to_swear_deck(&self) -> Deck<'rt> {
-		Deck::default()	
+		self.entries()
 	}
 
 	fn to_swear_map(&self) -> Map<'rt> {
@@ -62,12 +100,109 @@ impl<'rt> Map<'rt> {
 			// Lest function.
 			// No op, returns this Object.
 			"lest" => 
-				FunctionInfoBuilder::new("lest".to_string()).build_native(Arc::new(Mutex::new(|obj, _| Ok(Some(obj))))),
+				FunctionInfoBuilder::new("lest".to_string()).build_native(Arc::new(Mutex::new(|obj, _, _| Ok(Some(obj))))),
 
 			// Solid function.
 			// Returns false if Zip.
 			"solid" => 
-				FunctionInfoBuilder::new("solid".to_string()).build_native(Arc::new(Mutex::new(|_, _| Ok(Some(Object::from(State::from(true)).into()))))),
+				FunctionInfoBuilder::new("solid".to_string()).build_native(Arc::new(Mutex::new(|_, _, _| Ok(Some(Object::from(State::from(true)).into()))))),
+
+			// Get function.
+			// Returns the value under the given key, or Zip if there isn't one.
+			"get" => FunctionInfoBuilder::new("get".to_string()).build_native(Arc::new(Mutex::new(|obj: ObjectRef<'rt>, args: Vec<ObjectRef<'rt>>, _| {
+				let key = args.first().ok_or(())?;
+
+				let map_lock = obj.access();
+				let map = map_lock.as_map().unwrap();
+
+				Ok(Some(match map.map.get(key) {
+					Some(value) => value.copy(),
+					None => Object::from(Zip).into(),
+				}))
+			}))),
+
+			// Set function.
+			// Puts a value under the given key, replacing any value already there.
+			"set" => FunctionInfoBuilder::new("set".to_string()).build_native(Arc::new(Mutex::new(|obj: ObjectRef<'rt>, args: Vec<ObjectRef<'rt>>, _| {
+				let [key, value] = <[_; 2]>::try_from(args).map_err(|_| ())?;
+				let key = self::key(&key);
+
+				let mut map_lock = obj.lock();
+				let map = map_lock.as_map_mut().unwrap();
+
+				map.map.insert(key, value);
+
+				drop(map_lock);
+
+				Ok(Some(obj))
+			}))),
+
+			// Remove function.
+			// Removes and returns the value under the given key, or Zip if there isn't one.
+			"remove" => FunctionInfoBuilder::new("remove".to_string()).build_native(Arc::new(Mutex::new(|obj: ObjectRef<'rt>, args: Vec<ObjectRef<'rt>>, _| {
+				let key = args.first().ok_or(())?;
+
+				let mut map_lock = obj.lock();
+				let map = map_lock.as_map_mut().unwrap();
+
+				Ok(Some(map.map.remove(key).unwrap_or_else(|| Object::from(Zip).into())))
+			}))),
+
+			// Has function.
+			// Returns true if there is a value under the given key.
+			"has" => FunctionInfoBuilder::new("has".to_string()).build_native(Arc::new(Mutex::new(|obj: ObjectRef<'rt>, args: Vec<ObjectRef<'rt>>, _| {
+				let key = args.first().ok_or(())?;
+
+				let map_lock = obj.access();
+				let map = map_lock.as_map().unwrap();
+
+				Ok(Some(Object::from(State::from(map.map.contains_key(key))).into()))
+			}))),
+
+			// Keys function.
+			// Returns a Deck of copies of the keys, in order.
+			"keys" => FunctionInfoBuilder::new("keys".to_string()).build_native(Arc::new(Mutex::new(|obj: ObjectRef<'rt>, _, _| {
+				let map_lock = obj.access();
+				let map = map_lock.as_map().unwrap();
+
+				Ok(Some(Object::from(Deck::from_iter_ref(map.map.keys().map(key))).into()))
+			}))),
+
+			// Values function.
+			// Returns a Deck of the values, in key order.
+			"values" => FunctionInfoBuilder::new("values".to_string()).build_native(Arc::new(Mutex::new(|obj: ObjectRef<'rt>, _, _| {
+				let map_lock = obj.access();
+				let map = map_lock.as_map().unwrap();
+
+				Ok(Some(Object::from(Deck::from_iter_ref(map.map.values().cloned())).into()))
+			}))),
+
+			// Entries function.
+			// Returns a Deck with a Deck of key and value for each entry, in key order.
+			"entries" => FunctionInfoBuilder::new("entries".to_string()).build_native(Arc::new(Mutex::new(|obj: ObjectRef<'rt>, _, _| {
+				let map_lock = obj.access();
+				let map = map_lock.as_map().unwrap();
+
+				Ok(Some(Object::from(map.entries()).into()))
+			}))),
+
+			// Merge function.
+			// Copies every entry of the arguments into this map, later arguments win.
+			"merge" => FunctionInfoBuilder::new("merge".to_string()).build_native(Arc::new(Mutex::new(|obj: ObjectRef<'rt>, args: Vec<ObjectRef<'rt>>, _| {
+				//? Converted before locking, an argument might be this map.
+				let others: Vec<Map> = args.iter().map(|arg| arg.access().to_map()).collect();
+
+				let mut map_lock = obj.lock();
+				let map = map_lock.as_map_mut().unwrap();
+
+				for other in others {
+					map.map.extend(other.map);
+				}
+
+				drop(map_lock);
+
+				Ok(Some(obj))
+			}))),
 
 			_ => return None,
 		})
@@ -87,13 +222,13 @@ impl<'rt> std::fmt::Debug for Map<'rt> {
 
 impl<'rt> From<Vec<(ObjectRef<'rt>, ObjectRef<'rt>)>> for Map<'rt> {
 	fn from(map: Vec<(ObjectRef<'rt>, ObjectRef<'rt>)>) -> Self {
-		Self { map }
+		Self { map: map.into_iter().map(|(k, v)| (key(&k), v)).collect() }
 	}
 }
 
 impl<'rt> From<Vec<(ObjectRef<'rt>, Object<'rt>)>> for Map<'rt> {
 	fn from(map: Vec<(ObjectRef<'rt>, Object<'rt>)>) -> Self {
-		Self { map: map.into_iter().map(|(k, v)| (k, ObjectRef::from(v))).collect() }
+		Self { map: map.into_iter().map(|(k, v)| (key(&k), ObjectRef::from(v))).collect() }
 	}
 }
 
@@ -111,13 +246,13 @@ impl<'rt> From<Vec<(Object<'rt>, Object<'rt>)>> for Map<'rt> {
 
 impl<'rt> FromIterator<(ObjectRef<'rt>, ObjectRef<'rt>)> for Map<'rt> {
 	fn from_iter<T: IntoIterator<Item = (ObjectRef<'rt>, ObjectRef<'rt>)>>(iter: T) -> Self {
-		Self { map: iter.into_iter().collect() }
+		Self { map: iter.into_iter().map(|(k, v)| (key(&k), v)).collect() }
 	}
 }
 
 impl<'rt> FromIterator<(ObjectRef<'rt>, Object<'rt>)> for Map<'rt> {
 	fn from_iter<T: IntoIterator<Item = (ObjectRef<'rt>, Object<'rt>)>>(iter: T) -> Self {
-		Self { map: iter.into_iter().map(|(k, v)| (k, ObjectRef::from(v))).collect() }
+		Self { map: iter.into_iter().map(|(k, v)| (key(&k), ObjectRef::from(v))).collect() }
 	}
 }
 
@@ -132,3 +267,132 @@ impl<'rt> FromIterator<(Object<'rt>, Object<'rt>)> for Map<'rt> {
 		Self { map: iter.into_iter().map(|(k, v)| (ObjectRef::from(k), ObjectRef::from(v))).collect() }
 	}
 }
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn count(count: i64) -> ObjectRef<'static> {
+		Object::from(Count::from(count)).into()
+	}
+
+	fn chars(chars: &str) -> ObjectRef<'static> {
+		Object::from(Chars::from(chars.to_string())).into()
+	}
+
+	fn deck(items: &[i64]) -> ObjectRef<'static> {
+		Object::from(Deck::from_iter_ref(items.iter().map(|item| count(*item)))).into()
+	}
+
+	/// A Map of Chars keys to Count values.
+	fn map(entries: &[(&str, i64)]) -> ObjectRef<'static> {
+		Object::from(Map::from_iter(entries.iter().map(|(k, v)| (chars(k), count(*v))))).into()
+	}
+
+	fn call(obj: &ObjectRef<'static>, name: &str, args: Vec<ObjectRef<'static>>) -> Result<ObjectRef<'static>, ()> {
+		Ok(call_native(obj, name, args)?.expect("Expected a result"))
+	}
+
+	/// The items of a Deck, as Debug output.
+	fn items(obj: &ObjectRef) -> Vec<String> {
+		obj.access().to_deck().deck.iter().map(|item| format!("{:?}", item)).collect()
+	}
+
+	#[test]
+	fn test_get_set() {
+		let obj = map(&[("a", 1)]);
+
+		assert_eq!(call(&obj, "get", vec![chars("a")]).map(|v| v.access().to_count()), Ok(Count::from(1)));
+		assert!(call(&obj, "get", vec![chars("b")]).expect("Failed to get").access().is_zip());
+
+		let result = call(&obj, "set", vec![chars("a"), count(2)]).expect("Failed to set");
+		assert!(result.ptr_eq(&obj));
+		call(&obj, "set", vec![chars("b"), count(3)]).expect("Failed to set");
+
+		assert_eq!(call(&obj, "get", vec![chars("a")]).map(|v| v.access().to_count()), Ok(Count::from(2)));
+		assert_eq!(call(&obj, "get", vec![chars("b")]).map(|v| v.access().to_count()), Ok(Count::from(3)));
+		assert_eq!(obj.access().to_count(), Count::from(2));
+
+		assert!(call(&obj, "get", vec![]).is_err());
+		assert!(call(&obj, "set", vec![chars("a")]).is_err());
+	}
+
+	#[test]
+	fn test_remove_has() {
+		let obj = map(&[("a", 1), ("b", 2)]);
+
+		assert!(call(&obj, "has", vec![chars("a")]).expect("Failed to check").access().to_state().state);
+		assert_eq!(call(&obj, "remove", vec![chars("a")]).map(|v| v.access().to_count()), Ok(Count::from(1)));
+		assert!(!call(&obj, "has", vec![chars("a")]).expect("Failed to check").access().to_state().state);
+		assert!(call(&obj, "remove", vec![chars("a")]).expect("Failed to remove").access().is_zip());
+		assert_eq!(obj.access().to_count(), Count::from(1));
+	}
+
+	/// Test that keys, values and entries all come in key order.
+	#[test]
+	fn test_listing() {
+		let obj = map(&[("b", 2), ("c", 3), ("a", 1)]);
+
+		let keys = call(&obj, "keys", vec![]).expect("Failed to list keys");
+		assert_eq!(items(&keys), items(&Object::from(Deck::from_iter_ref(["a", "b", "c"].map(chars))).into()));
+
+		let values = call(&obj, "values", vec![]).expect("Failed to list values");
+		assert_eq!(items(&values), items(&deck(&[1, 2, 3])));
+
+		let entries = call(&obj, "entries", vec![]).expect("Failed to list entries");
+		let entries = entries.access().to_deck().deck;
+		assert_eq!(entries.len(), 3);
+		let first = entries[0].access().to_deck().deck;
+		assert_eq!(first[0].access().to_chars().chars, "a");
+		assert_eq!(first[1].access().to_count(), Count::from(1));
+	}
+
+	/// Test that later arguments win, and that a map can be merged with itself.
+	#[test]
+	fn test_merge() {
+		let obj = map(&[("a", 1), ("b", 2)]);
+
+		let result = call(&obj, "merge", vec![map(&[("b", 3), ("c", 4)]), map(&[("c", 5)])]).expect("Failed to merge");
+		assert!(result.ptr_eq(&obj));
+
+		let values = call(&obj, "values", vec![]).expect("Failed to list values");
+		assert_eq!(items(&values), items(&deck(&[1, 3, 5])));
+
+		call(&obj, "merge", vec![obj.copy()]).expect("Failed to merge with itself");
+		assert_eq!(obj.access().to_count(), Count::from(3));
+	}
+
+	/// Test that changing any part of an Object used as a key, or of one handed out by `keys`, leaves the map alone.
+	#[test]
+	fn test_key_aliasing() {
+		let obj = map(&[]);
+		let key = deck(&[1, 2]);
+
+		call(&obj, "set", vec![key.copy(), count(3)]).expect("Failed to set");
+		let first = key.access().to_deck().deck[0].copy();
+		*first.lock() = Object::from(Count::from(9));
+
+		assert!(call(&obj, "get", vec![key.copy()]).expect("Failed to get").access().is_zip());
+		assert_eq!(call(&obj, "get", vec![deck(&[1, 2])]).map(|v| v.access().to_count()), Ok(Count::from(3)));
+
+		let keys = call(&obj, "keys", vec![]).expect("Failed to list keys");
+		let listed = keys.access().to_deck().deck[0].copy();
+		*listed.access().to_deck().deck[1].lock() = Object::from(Count::from(9));
+
+		assert_eq!(call(&obj, "get", vec![deck(&[1, 2])]).map(|v| v.access().to_count()), Ok(Count::from(3)));
+	}
+
+	/// Test that Dynamics are keys by identity, even inside another key.
+	#[test]
+	fn test_dynamic_keys() {
+		let dynamic: ObjectRef = Object::from(Dynamic::default()).into();
+		let key: ObjectRef = Object::from(Deck::from_iter_ref([dynamic.copy()])).into();
+
+		let obj = map(&[]);
+		call(&obj, "set", vec![key.copy(), count(1)]).expect("Failed to set");
+
+		let keys = call(&obj, "keys", vec![]).expect("Failed to list keys");
+		let listed = keys.access().to_deck().deck[0].access().to_deck().deck[0].copy();
+		assert!(listed.ptr_eq(&dynamic));
+	}
+}
@@ -5,6 +5,8 @@ mod zip;
 mod deck;
 mod map;
 mod dynamic;
+mod callable;
+mod convert;
 
 use std::sync::{Arc, Mutex};
 use std::collections::BTreeMap as HashMap;
@@ -16,14 +18,17 @@ pub use zip::*;
 pub use deck::*;
 pub use map::*;
 pub use dynamic::*;
+pub use callable::*;
+pub use convert::*;
 
-pub type ObjectFunction<'rt> = dyn FnMut(ObjectRef<'rt>, Vec<ObjectRef<'rt>>) -> Result<Option<ObjectRef<'rt>>, ()>;
+pub type ObjectFunction<'rt> = dyn FnMut(ObjectRef<'rt>, Vec<ObjectRef<'rt>>, Invoker<'rt>) -> NativeResult<'rt>;
 
 use enum_dispatch::enum_dispatch;
 use swear_parser::ObjectLiteral;
 use swear_lib_macros::swear_object;
 
 use crate::context::{Callback, NativeCallback, ObjectRef};
+use crate::runtime::{Invoker, NativeResult};
 
 #[enum_dispatch]
 #[derive(Clone)]
@@ -36,6 +41,7 @@ pub enum Object<'rt> {
 	Deck(Deck<'rt>),
 	Map(Map<'rt>),
 	Dynamic(Dynamic<'rt>),
+	Callable(Callable<'rt>),
 }
 
 impl<'rt> PartialEq for Object<'rt> {
@@ -47,18 +53,79 @@ impl<'rt> PartialEq for Object<'rt> {
 			(Object::Zip(a), Object::Zip(b)) => a == b,
 			(Object::Deck(a), Object::Deck(b)) => a == b,
 			(Object::Map(a), Object::Map(b)) => a == b,
-			(Object::Dynamic(_), Object::Dynamic(_)) => false,
+			//? Dynamics have no value to compare, so each is only equal to itself.
+			//? An `equals` callback can't be run from here, so Map keys and sorting go by identity too;
+			//? Deck's `contains` and `find` are what call it.
+			(Object::Dynamic(a), Object::Dynamic(b)) => std::ptr::eq(a, b),
+			(Object::Callable(a), Object::Callable(b)) => a == b,
 			_ => false,
 		}
 	}
 }
 impl<'rt> Eq for Object<'rt> {}
 
+impl<'rt> PartialOrd for Object<'rt> {
+	fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+		Some(self.cmp(other))
+	}
+}
+
+//? Objects of different kinds are ordered by kind, so a mixed Deck sorts into groups.
+impl<'rt> Ord for Object<'rt> {
+	fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+		match (self, other) {
+			(Object::Chars(a), Object::Chars(b)) => a.cmp(b),
+			(Object::Count(a), Object::Count(b)) => a.cmp(b),
+			(Object::State(a), Object::State(b)) => a.cmp(b),
+			(Object::Zip(a), Object::Zip(b)) => a.cmp(b),
+			(Object::Deck(a), Object::Deck(b)) => a.deck.cmp(&b.deck),
+			(Object::Map(a), Object::Map(b)) => a.map.cmp(&b.map),
+			//? Told apart by address, to agree with PartialEq.
+			(Object::Dynamic(a), Object::Dynamic(b)) => std::ptr::from_ref(a).cmp(&std::ptr::from_ref(b)),
+			(Object::Callable(a), Object::Callable(b)) => a.cmp(b),
+			(a, b) => a.rank().cmp(&b.rank()),
+		}
+	}
+}
+
+//? Must agree with PartialEq, Map keys rely on it.
+impl<'rt> std::hash::Hash for Object<'rt> {
+	fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+		self.rank().hash(state);
+		match self {
+			Object::Chars(c) => c.hash(state),
+			Object::Count(c) => c.hash(state),
+			Object::State(s) => s.hash(state),
+			Object::Zip(z) => z.hash(state),
+			Object::Deck(d) => d.deck.hash(state),
+			Object::Map(m) => m.map.hash(state),
+			Object::Dynamic(_) => {},
+			Object::Callable(c) => c.name.hash(state),
+		}
+	}
+}
+
+impl<'rt> Object<'rt> {
+	/// Where this kind of Object sorts relative to the others.
+	fn rank(&self) -> u8 {
+		match self {
+			Object::Zip(_) => 0,
+			Object::State(_) => 1,
+			Object::Count(_) => 2,
+			Object::Chars(_) => 3,
+			Object::Deck(_) => 4,
+			Object::Map(_) => 5,
+			Object::Dynamic(_) => 6,
+			Object::Callable(_) => 7,
+		}
+	}
+}
+
 impl<'rt> Object<'rt> {
 	pub fn from_literal(literal: &ObjectLiteral) -> Self {
 		match literal {
 			ObjectLiteral::Chars(c) => Chars::from(c.clone()).into(),
-			ObjectLiteral::Count(c) => Count::from(*c as i64).into(), //FIXME
+			ObjectLiteral::Count(c) => Count::parse(c).unwrap_or_else(|| Count::from(1)).into(),
 			ObjectLiteral::State(s) => State::from(*s).into(),
 			ObjectLiteral::Zip => Zip.into(),
 			ObjectLiteral::Deck(d) => Deck::from_vec_lit(d).into(),
@@ -77,6 +144,7 @@ impl<'rt> std::fmt::Debug for Object<'rt> {
 			Object::Deck(d) => write!(f, "Obj({:?})", d),
 			Object::Map(m) => write!(f, "Obj({:?})", m),
 			Object::Dynamic(d) => write!(f, "Obj({:?})", d),
+			Object::Callable(c) => write!(f, "Obj({:?})", c),
 		}
 	}
 }
@@ -110,6 +178,9 @@ pub trait IObject<'rt> {
 	fn is_dynamic(&self) -> bool {
 		false
 	}
+	fn is_prototype(&self) -> bool {
+		false
+	}
 
 	fn get_info(&self) -> ObjectInfo;
 
@@ -215,6 +286,7 @@ mod serde_impl {
 				Object::Deck(d) => d.serialize(serializer),
 				Object::Map(m) => m.serialize(serializer),
 				Object::Dynamic(d) => d.serialize(serializer),
+				Object::Callable(c) => c.serialize(serializer),
 			}
 		}
 	}
@@ -229,3 +301,15 @@ mod serde_impl {
 		}
 	}
 }
+
+/// Calls one of the Object's native functions the way the runtime would, so they can be tested on their own.
+#[cfg(test)]
+pub(crate) fn call_native<'rt>(obj: &ObjectRef<'rt>, name: &str, args: Vec<ObjectRef<'rt>>) -> NativeResult<'rt> {
+	let Some(function) = obj.access().get_function(name) else {
+		panic!("Expected a function named `{name}`");
+	};
+	let Callback::Native(native) = function.function else {
+		panic!("Expected `{name}` to be native");
+	};
+	native.callback.lock().expect("Failed to lock the function").call_mut((obj.copy(), args, Invoker::default()))
+}
@@ -1,7 +1,7 @@
 use super::*;
 
 #[cfg_attr(feature="serde", derive(serde::Serialize, serde::Deserialize))]
-#[derive(Clone, Default, PartialEq, Eq)]
+#[derive(Clone, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
 #[swear_object]
 pub struct State {
 	pub state: bool,
@@ -39,7 +39,7 @@ impl<'rt> State {
 			// And function.
 			// Returns true if this object and all arguments are true.
 			"and" =>
-				FunctionInfoBuilder::new("and".to_string()).build_native(Arc::new(Mutex::new(|obj: ObjectRef<'rt>, args: Vec<ObjectRef<'rt>>| {
+				FunctionInfoBuilder::new("and".to_string()).build_native(Arc::new(Mutex::new(|obj: ObjectRef<'rt>, args: Vec<ObjectRef<'rt>>, _| {
 					let state_lock = obj.access();
 					let state = state_lock.as_state().unwrap();
 
@@ -59,6 +59,65 @@ impl<'rt> State {
 					Ok(Some(Object::from(State::from(true)).into()))
 				}))),
 
+			// Or function.
+			// Returns true if this object or any argument is true.
+			"or" =>
+				FunctionInfoBuilder::new("or".to_string()).build_native(Arc::new(Mutex::new(|obj: ObjectRef<'rt>, args: Vec<ObjectRef<'rt>>, _| {
+					let state = obj.access().to_state().state;
+					let any = state || args.iter().any(|arg| arg.access().to_state().state);
+
+					Ok(Some(Object::from(State::from(any)).into()))
+				}))),
+
+			// Nand function.
+			// Returns false if this object and all arguments are true.
+			"nand" =>
+				FunctionInfoBuilder::new("nand".to_string()).build_native(Arc::new(Mutex::new(|obj: ObjectRef<'rt>, args: Vec<ObjectRef<'rt>>, _| {
+					let state = obj.access().to_state().state;
+					let all = state && args.iter().all(|arg| arg.access().to_state().state);
+
+					Ok(Some(Object::from(State::from(!all)).into()))
+				}))),
+
+			// Xor function.
+			// Returns true if an odd number of this object and the arguments are true.
+			"xor" =>
+				FunctionInfoBuilder::new("xor".to_string()).build_native(Arc::new(Mutex::new(|obj: ObjectRef<'rt>, args: Vec<ObjectRef<'rt>>, _| {
+					let state = obj.access().to_state().state;
+					let odd = args.iter().fold(state, |odd, arg| odd ^ arg.access().to_state().state);
+
+					Ok(Some(Object::from(State::from(odd)).into()))
+				}))),
+
+			// Not function.
+			// Returns the opposite of this object.
+			"not" =>
+				FunctionInfoBuilder::new("not".to_string()).build_native(Arc::new(Mutex::new(|obj: ObjectRef<'rt>, _, _| {
+					let state = obj.access().to_state().state;
+
+					Ok(Some(Object::from(State::from(!state)).into()))
+				}))),
+
+			// Equals function.
+			// Returns true if all arguments have the same state as this object.
+			"equals" =>
+				FunctionInfoBuilder::new("equals".to_string()).build_native(Arc::new(Mutex::new(|obj: ObjectRef<'rt>, args: Vec<ObjectRef<'rt>>, _| {
+					let state = obj.access().to_state().state;
+					let all = args.iter().all(|arg| arg.access().to_state().state == state);
+
+					Ok(Some(Object::from(State::from(all)).into()))
+				}))),
+
+			// Lest function.
+			// No op, returns this Object.
+			"lest" =>
+				FunctionInfoBuilder::new("lest".to_string()).build_native(Arc::new(Mutex::new(|obj, _, _| Ok(Some(obj))))),
+
+			// Solid function.
+			// Returns false if Zip.
+			"solid" =>
+				FunctionInfoBuilder::new("solid".to_string()).build_native(Arc::new(Mutex::new(|_, _, _| Ok(Some(Object::from(State::from(true)).into()))))),
+
 			_ => return None,
 		})
 	}
@@ -111,3 +170,45 @@ impl From<bool> for State {
 		Self { state }
 	}
 }
+
+#[cfg(all(test, feature="parser"))]
+mod tests {
+	use super::*;
+	use crate::runtime::{ContextStack, SwearRuntime};
+	use swear_parser::SwearParser;
+
+	/// Defines `yes` and `no`, and `touch`, which returns `yes` after leaving a mark in `marks`.
+	const PRELUDE: &str = "yes% '1'#>equals '1'#*<\nno% '1'#>equals '0'#*<\nmarks% ''|\ntouch! [\n\tmarks>push 'x'$*<\n\tyes\n]\n";
+
+	/// Runs a script after the prelude, returning the result and how many times `touch` was called.
+	fn run(script: &str) -> (bool, usize) {
+		let script = SwearParser::new().parse(&format!("{PRELUDE}{script}\nmarks>push result*<")).expect("Failed to parse input");
+		let mut runtime = ContextStack::new(Box::leak(Box::new(script)));
+		while !runtime.is_finished() {
+			runtime.step().expect("Failed to run script");
+		}
+
+		let marks = runtime.take_result().expect("Expected the marks").to_deck().deck;
+		let (result, marks) = marks.split_last().expect("Expected the result");
+		(result.access().to_state().state, marks.len())
+	}
+
+	/// Test that an argument is only evaluated if the ones before it didn't decide the result.
+	#[test]
+	fn test_unevaluated() {
+		assert_eq!(run("result% no>and >touch<*<"), (false, 0));
+		assert_eq!(run("result% yes>or >touch<*<"), (true, 0));
+		assert_eq!(run("result% no>nand >touch<*<"), (true, 0));
+		assert_eq!(run("result% yes>and no* >touch<*<"), (false, 0));
+		assert_eq!(run("result% no>or yes* >touch<*<"), (true, 0));
+	}
+
+	/// Test that arguments that could change the result are still evaluated, once each.
+	#[test]
+	fn test_evaluated() {
+		assert_eq!(run("result% yes>and >touch<*<"), (true, 1));
+		assert_eq!(run("result% no>or >touch<*<"), (true, 1));
+		assert_eq!(run("result% yes>and >touch<* >touch<*<"), (true, 2));
+		assert_eq!(run("result% no>xor >touch<*<"), (true, 1));
+	}
+}
@@ -1,7 +1,7 @@
 use super::*;
 
 #[cfg_attr(feature="serde", derive(serde::Serialize, serde::Deserialize))]
-#[derive(Clone, Default, PartialEq, Eq)]
+#[derive(Clone, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
 // #[swear_object]
 pub struct Zip;
 
@@ -39,7 +39,7 @@ impl<'rt> IObject<'rt> for Zip {
 		Some(match name {
 			// Lest function.
 			// Returns the first argument.
-			"lest" => FunctionInfoBuilder::new("lest".to_string()).build_native(Arc::new(Mutex::new(|_, args: Vec<ObjectRef<'rt>>|
+			"lest" => FunctionInfoBuilder::new("lest".to_string()).build_native(Arc::new(Mutex::new(|_, args: Vec<ObjectRef<'rt>>, _|
 				match args.first() {
 					Some(arg) => Ok(Some(arg.copy())),
 					None => Err(()),
@@ -48,7 +48,7 @@ impl<'rt> IObject<'rt> for Zip {
 
 			// Solid function.
 			// Returns false if Zip.
-			"solid" => FunctionInfoBuilder::new("solid".to_string()).build_native(Arc::new(Mutex::new(|_, _| Ok(Some(Object::from(State::from(false)).into())) ))),
+			"solid" => FunctionInfoBuilder::new("solid".to_string()).build_native(Arc::new(Mutex::new(|_, _, _| Ok(Some(Object::from(State::from(false)).into())) ))),
 
 			_ => return None,
 		})
@@ -0,0 +1,141 @@
+use std::fmt::Display;
+use swear_parser::Span;
+
+/// An error raised while stepping a runtime.
+///
+/// These are recoverable from the host's perspective; the runtime is left
+/// in whatever state it was in when the failing operation was popped.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SwearError {
+	/// A callback was invoked by name, but nothing callable was found.
+	UnknownCallback {
+		name: String,
+		span: Span,
+	},
+	/// An operation expected a value on the table, but it was empty.
+	StackUnderflow,
+	/// A lock guarding an Object or native callback was poisoned by a panic.
+	LockPoisoned,
+	/// A native callback reported a failure.
+	NativeCallbackFailed {
+		name: String,
+		span: Span,
+	},
+	/// A `break` or `continue` was used without a loop around it.
+	OutsideLoop {
+		control: &'static str,
+		span: Span,
+	},
+	/// An imported module wasn't in any of the directories searched.
+	ModuleNotFound {
+		name: String,
+		span: Span,
+	},
+	/// A module was imported while it was still being loaded.
+	/// The chain lists each module on the way, starting and ending with the same one.
+	CyclicImport {
+		chain: Vec<String>,
+		span: Span,
+	},
+	/// An imported module couldn't be read, parsed or run.
+	ModuleFailed {
+		name: String,
+		/// Where in the module it went wrong, as `path:line:column`, if that's known.
+		at: Option<String>,
+		reason: ModuleFailure,
+		span: Span,
+	},
+	/// A native library wasn't in any of the directories searched.
+	LibraryNotFound {
+		name: String,
+		span: Span,
+	},
+	/// A native library couldn't be loaded, or loading them is turned off.
+	LibraryFailed {
+		name: String,
+		reason: String,
+		span: Span,
+	},
+	/// A value was used somewhere it doesn't fit.
+	TypeMismatch {
+		expected: String,
+		found: String,
+	},
+}
+
+impl Display for SwearError {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		match self {
+			SwearError::UnknownCallback { name, .. } => write!(f, "callback `{name}` was not found"),
+			SwearError::StackUnderflow => write!(f, "tried to take a value from an empty table"),
+			SwearError::LockPoisoned => write!(f, "a lock was poisoned by an earlier panic"),
+			SwearError::NativeCallbackFailed { name, .. } => write!(f, "native callback `{name}` failed"),
+			SwearError::OutsideLoop { control, .. } => write!(f, "`{control}` used outside of a loop"),
+			SwearError::ModuleNotFound { name, .. } => write!(f, "module `{name}` was not found"),
+			SwearError::CyclicImport { chain, .. } => write!(f, "cyclic import: {}", chain.join(" -> ")),
+			SwearError::ModuleFailed { name, at, reason, .. } => {
+				write!(f, "failed to import `{name}`")?;
+				if let ModuleFailure::Load(reason) = reason {
+					write!(f, ": {reason}")?;
+				}
+				match at {
+					Some(at) => write!(f, " at {at}"),
+					None => Ok(()),
+				}
+			},
+			SwearError::LibraryNotFound { name, .. } => write!(f, "native library `{name}` was not found"),
+			SwearError::LibraryFailed { name, reason, .. } => write!(f, "failed to load `{name}`: {reason}"),
+			SwearError::TypeMismatch { expected, found } => write!(f, "expected {expected}, found {found}"),
+		}
+	}
+}
+
+impl std::error::Error for SwearError {
+	fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+		match self {
+			SwearError::ModuleFailed { reason: ModuleFailure::Run(error), .. } => Some(error.as_ref()),
+			_ => None,
+		}
+	}
+}
+
+/// Why an imported module failed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ModuleFailure {
+	/// The file couldn't be read, or didn't parse.
+	Load(String),
+	/// It ran into an error, which is the [source](std::error::Error::source) of the one it failed with.
+	Run(Box<SwearError>),
+}
+
+impl SwearError {
+	/// The part of the script this error was raised for, if it's known.
+	pub fn span(&self) -> Option<Span> {
+		match self {
+			SwearError::UnknownCallback { span, .. } |
+			SwearError::NativeCallbackFailed { span, .. } |
+			SwearError::OutsideLoop { span, .. } |
+			SwearError::ModuleNotFound { span, .. } |
+			SwearError::CyclicImport { span, .. } |
+			SwearError::ModuleFailed { span, .. } |
+			SwearError::LibraryNotFound { span, .. } |
+			SwearError::LibraryFailed { span, .. } => Some(*span),
+			_ => None,
+		}
+	}
+}
+
+impl<T> From<std::sync::PoisonError<T>> for SwearError {
+	fn from(_: std::sync::PoisonError<T>) -> Self {
+		SwearError::LockPoisoned
+	}
+}
+
+/// What happened during a single call to [`SwearRuntime::step`](super::SwearRuntime::step).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StepOutcome {
+	/// An operation was handled and there is more work to do.
+	Running,
+	/// The root context has been popped and a result is available.
+	Finished,
+}
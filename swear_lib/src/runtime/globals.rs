@@ -0,0 +1,95 @@
+use super::*;
+
+//? Globals sit above the root scope, so a script can shadow them without changing them for anyone else.
+impl<'rt> ContextStack<'rt> {
+	/// Defines a native function scripts can call by name, like `>now<`.
+	/// When called without a target, it's given Zip in its place.
+	pub fn add_function(&mut self, info: FunctionInfo<'rt>) -> &mut Self {
+		self.bind_global(info.name, ContextItem::Callback(info.function))
+	}
+
+	/// Defines a value scripts can use by name.
+	pub fn add_value(&mut self, name: impl Into<String>, value: impl Into<Object<'rt>>) -> &mut Self {
+		self.bind_global(name.into(), ObjectRef::new(value.into()).into())
+	}
+
+	/// Defines a blueprint scripts can build by name, with a body parsed ahead of time.
+	/// Any `parents` have to be globals as well.
+	pub fn add_blueprint(&mut self, name: impl Into<String>, parameters: Vec<String>, parents: Vec<String>, body: &'rt Expression) -> &mut Self {
+		let blueprint = Blueprint {
+			args: parameters,
+			parents,
+			expr: body,
+			scope: self.globals.clone(),
+		};
+		self.bind_global(name.into(), ContextItem::Blueprint(blueprint))
+	}
+
+	//? The globals can only be poisoned by a panic, which the host has already seen.
+	fn bind_global(&mut self, name: String, item: ContextItem<'rt>) -> &mut Self {
+		let _ = self.globals.bind(name, item);
+		self
+	}
+}
+
+#[cfg(all(test, feature="parser"))]
+mod tests {
+	use std::sync::{Arc, Mutex};
+
+	use super::*;
+	use swear_parser::SwearParser;
+
+	fn parse(source: &str) -> &'static Expression {
+		Box::leak(Box::new(SwearParser::new().parse(source).expect("Failed to parse input")))
+	}
+
+	fn run(runtime: &mut ContextStack<'static>) -> Result<Option<Object<'static>>, SwearError> {
+		while !runtime.is_finished() {
+			runtime.step()?;
+		}
+		Ok(runtime.take_result())
+	}
+
+	/// Test that a global function called without a target is given Zip as its target.
+	#[test]
+	fn test_function_target() {
+		let mut runtime = ContextStack::new(parse(">now<"));
+		runtime.add_function(FunctionInfoBuilder::new("now".to_string()).build_native(Arc::new(Mutex::new(|obj, _, _| Ok(Some(obj))))));
+
+		assert_eq!(run(&mut runtime), Ok(Some(Object::from(Zip))));
+	}
+
+	/// Test that a script shadowing a global only changes it for itself, not for the modules it imports.
+	#[test]
+	fn test_shadowing() {
+		let dir = std::env::temp_dir().join(format!("swear_globals_{}", std::process::id()));
+		std::fs::create_dir_all(&dir).expect("Failed to create the module directory");
+		std::fs::write(dir.join("seen.sw"), "limit% limit").expect("Failed to write a module");
+
+		let script = |source: &str| {
+			let mut runtime = ContextStack::new(parse(source));
+			runtime.add_value("limit", Count::from(3)).add_module_path(&dir);
+			run(&mut runtime)
+		};
+
+		assert_eq!(script("limit% '9'#\nlimit"), Ok(Some(Object::from(Count::from(9)))));
+		assert_eq!(script("limit% '9'#\nm% >import 'seen'$*<\nm>get 'limit'$*<"), Ok(Some(Object::from(Count::from(3)))));
+		let _ = std::fs::remove_dir_all(dir);
+	}
+
+	/// Test that a global blueprint can extend another, and gets its parameters.
+	#[test]
+	fn test_blueprint_parent() {
+		let field = |name: &str| {
+			let mut runtime = ContextStack::new(parse(&format!("c% >Child '4'#*<\nc>get '{name}'$*<")));
+			runtime
+				.add_blueprint("Base", vec![], vec![], parse("kind% 'base'$\nlegs% '2'#"))
+				.add_blueprint("Child", vec!["n".to_string()], vec!["Base".to_string()], parse("kind% 'child'$\nsize% n"));
+			run(&mut runtime)
+		};
+
+		assert_eq!(field("kind"), Ok(Some(Object::from(Chars::from("child")))));
+		assert_eq!(field("size"), Ok(Some(Object::from(Count::from(4)))));
+		assert_eq!(field("legs"), Ok(Some(Object::from(Count::from(2)))));
+	}
+}
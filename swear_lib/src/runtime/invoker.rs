@@ -0,0 +1,105 @@
+use std::sync::{Arc, Mutex};
+
+use crate::context::ObjectRef;
+
+/// What a native callback, or a continuation of one, hands back to the runtime.
+pub type NativeResult<'rt> = Result<Option<ObjectRef<'rt>>, ()>;
+
+type ContinuationFn<'rt> = dyn FnOnce(ObjectRef<'rt>, Invoker<'rt>) -> NativeResult<'rt> + 'rt;
+
+/// Lets a native callback call back into Swear.
+///
+/// The runtime can't run a Swear callback while a native one is on the Rust stack,
+/// so calls are scheduled rather than made. Once the called callback has finished,
+/// its result is passed to the continuation, which can schedule another call or finish
+/// with a value of its own.
+///
+/// ```ignore
+/// |obj, args, invoker| {
+///     let callback = args[0].copy();
+///     invoker.invoke(callback, vec![obj], |result, _| Ok(Some(result)));
+///     Ok(None)
+/// }
+/// ```
+#[derive(Clone, Default)]
+pub struct Invoker<'rt> {
+	scheduled: Arc<Mutex<Option<Invocation<'rt>>>>,
+}
+
+impl<'rt> Invoker<'rt> {
+	/// Schedules `callback` to be called with `args` once the native callback returns.
+	/// `then` receives the result, and whatever it returns becomes the native callback's result.
+	///
+	/// A callback is either the name of one, as Chars, or a callable Object.
+	/// Only one call can be scheduled at a time, scheduling another replaces it.
+	/// While a call is scheduled, the value the native callback returns is ignored.
+	pub fn invoke(
+		&self,
+		callback: ObjectRef<'rt>,
+		args: Vec<ObjectRef<'rt>>,
+		then: impl FnOnce(ObjectRef<'rt>, Invoker<'rt>) -> NativeResult<'rt> + 'rt,
+	) {
+		self.schedule(None, callback, args, then);
+	}
+
+	/// Like [`Invoker::invoke`], but calls `callback` as a method of `target`, so it sees it as `self`.
+	pub fn invoke_on(
+		&self,
+		target: ObjectRef<'rt>,
+		callback: ObjectRef<'rt>,
+		args: Vec<ObjectRef<'rt>>,
+		then: impl FnOnce(ObjectRef<'rt>, Invoker<'rt>) -> NativeResult<'rt> + 'rt,
+	) {
+		self.schedule(Some(target), callback, args, then);
+	}
+
+	fn schedule(
+		&self,
+		target: Option<ObjectRef<'rt>>,
+		callback: ObjectRef<'rt>,
+		args: Vec<ObjectRef<'rt>>,
+		then: impl FnOnce(ObjectRef<'rt>, Invoker<'rt>) -> NativeResult<'rt> + 'rt,
+	) {
+		let invocation = Invocation {
+			target,
+			callback,
+			args,
+			then: Continuation(Arc::new(Mutex::new(Some(Box::new(then))))),
+		};
+
+		if let Ok(mut scheduled) = self.scheduled.lock() {
+			*scheduled = Some(invocation);
+		}
+	}
+
+	/// Takes the scheduled call, if there is one.
+	pub(crate) fn take(&self) -> Option<Invocation<'rt>> {
+		self.scheduled.lock().ok()?.take()
+	}
+}
+
+/// A call scheduled through an [`Invoker`].
+pub struct Invocation<'rt> {
+	pub target: Option<ObjectRef<'rt>>,
+	pub callback: ObjectRef<'rt>,
+	pub args: Vec<ObjectRef<'rt>>,
+	pub then: Continuation<'rt>,
+}
+
+/// The rest of a native callback, waiting on the result of a call it scheduled.
+#[derive(Clone)]
+pub struct Continuation<'rt>(Arc<Mutex<Option<Box<ContinuationFn<'rt>>>>>);
+
+impl<'rt> Continuation<'rt> {
+	/// Runs the continuation. It can only be resumed once, after that this returns `None`.
+	pub fn resume(&self, result: ObjectRef<'rt>, invoker: Invoker<'rt>) -> Option<NativeResult<'rt>> {
+		let then = self.0.lock().ok()?.take()?;
+		Some(then(result, invoker))
+	}
+}
+
+impl<'rt> std::fmt::Debug for Continuation<'rt> {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		write!(f, "Continuation")
+	}
+}
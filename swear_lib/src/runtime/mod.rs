@@ -1,24 +1,35 @@
 pub mod operations;
-
-
+mod error;
+mod invoker;
+mod modules;
+mod globals;
 
 pub use crate::context::ObjectRef;
+pub use error::*;
+pub use invoker::*;
+pub use modules::*;
 use crate::object::*;
 use crate::context::*;
 use operations::Operations;
-use swear_parser::{Definition, TopLevelItem, Valuable, Repetition, Expression};
+use operations::Logic;
+use swear_parser::{Definition, ObjectSymbol, TopLevelItem, TopLevelKind, Valuable, ValuableKind, Repetition, While, Expression, Span};
 
 pub trait SwearRuntime<'rt> {
 	fn new(script: &'rt Expression) -> Self;
-	fn step(&mut self);
+	fn step(&mut self) -> Result<StepOutcome, SwearError>;
 	fn next_operation(&self) -> Option<&Operations<'rt>>;
 	fn last_operation(&self) -> Option<&Operations<'rt>>;
 	fn instruction_index(&self) -> usize;
 	fn current_instruction(&self) -> Option<&TopLevelItem>;
+	fn current_span(&self) -> Option<Span>;
 	fn table(&self) -> &[ObjectRef<'rt>];
 	fn stack(&self) -> &[ContextHolder<'rt>];
 	fn is_finished(&self) -> bool;
 	fn get_result(self) -> Option<Object<'rt>>;
+	fn take_result(&mut self) -> Option<Object<'rt>>;
+	/// Runs more top level items against the existing root context.
+	/// Anything defined by earlier scripts stays visible to the new one.
+	fn append(&mut self, script: &'rt Expression);
 }
 
 #[derive(Debug)]
@@ -29,48 +40,32 @@ pub struct ContextStack<'rt> {
 	table: Vec<ObjectRef<'rt>>,
 	finished: bool,
 	result: Option<Object<'rt>>,
+	modules: std::sync::Arc<std::sync::Mutex<Modules<'rt>>>,
+	/// What the host defined for every script, the root scope is nested in it.
+	globals: Scope<'rt>,
 }
 
 
 
 impl<'rt> SwearRuntime<'rt> for ContextStack<'rt> {
 	fn new(script: &'rt Expression) -> Self {
+		let globals = Scope::default();
 		Self {
 			stack: vec![
-				ContextLevel::<'rt>::new(&script).into()
+				ContextLevel::<'rt>::new(&script, globals.child()).into()
 			],
 			at_root: true,
 			last_op: None,
 			table: Vec::new(),
 			finished: false,
 			result: None,
+			modules: Default::default(),
+			globals,
 		}
 	}
 
-	fn step(&mut self) {
-		if self.is_finished() {
-			return;
-		}
-
-		while self.ops().is_empty() {
-
-			let cont = self.runtime_cont();
-			if cont.instr_index() >= cont.instructions().len() {
-				self.pop();
-				if self.is_finished() {
-					return;
-				}
-
-				continue;
-			}
-
-			let instr = &cont.instructions()[cont.instr_index()];
-			self.process_instructions(&instr);
-
-			*self.runtime_cont_mut().instr_index_mut() += 1;
-		}
-
-		self.handle_next_op();
+	fn step(&mut self) -> Result<StepOutcome, SwearError> {
+		self.advance().map_err(|e| self.fail_modules(e))
 	}
 
 	fn next_operation(&self) -> Option<&Operations<'rt>> {
@@ -97,6 +92,16 @@ impl<'rt> SwearRuntime<'rt> for ContextStack<'rt> {
 		cont.instructions().get(cont.instr_index())
 	}
 
+	fn current_span(&self) -> Option<Span> {
+		if self.stack.is_empty() {
+			return None;
+		}
+
+		//? The index is bumped as soon as an instruction is processed, so the one running is just behind it.
+		let cont = self.runtime_cont();
+		cont.instructions().get(cont.instr_index().checked_sub(1)?).map(|instr| instr.span)
+	}
+
 	fn table(&self) -> &[ObjectRef<'rt>] {
 		&self.table
 	}
@@ -112,23 +117,86 @@ impl<'rt> SwearRuntime<'rt> for ContextStack<'rt> {
 	fn get_result(self) -> Option<Object<'rt>> {
 		self.result
 	}
+
+	fn take_result(&mut self) -> Option<Object<'rt>> {
+		self.result.take()
+	}
+
+	fn append(&mut self, script: &'rt Expression) {
+		//? Whatever was in flight is abandoned, it may have been left behind by an error.
+		self.stack.truncate(1);
+		self.table.clear();
+		self.at_root = true;
+		self.finished = false;
+		self.result = None;
+
+		let ContextHolder::RuntimeContext(RuntimeContext::ContextLevel(root)) = self.stack.first_mut().unwrap() else {
+			unreachable!("Stack had no root.");
+		};
+
+		root.instructions = script;
+		root.instr_index = 0;
+		root.ops.clear();
+	}
 }
 
 impl<'rt> ContextStack<'rt> {
+	/// Handles the next operation, processing instructions until there is one.
+	fn advance(&mut self) -> Result<StepOutcome, SwearError> {
+		if self.is_finished() {
+			return Ok(StepOutcome::Finished);
+		}
+
+		while self.ops().is_empty() {
+
+			let cont = self.runtime_cont();
+			if cont.instr_index() >= cont.instructions().len() {
+				self.pop()?;
+				if self.is_finished() {
+					return Ok(StepOutcome::Finished);
+				}
+
+				continue;
+			}
+
+			let instr = &cont.instructions()[cont.instr_index()];
+			self.process_instructions(&instr);
+
+			*self.runtime_cont_mut().instr_index_mut() += 1;
+		}
+
+		self.handle_next_op()?;
+
+		Ok(match self.is_finished() {
+			true => StepOutcome::Finished,
+			false => StepOutcome::Running,
+		})
+	}
+
 	fn process_instructions(&mut self, instruction: &'rt TopLevelItem) {
-		match instruction {
-			TopLevelItem::Definition(d) => self.process_instr_definition(d),
-			TopLevelItem::Valuable(v) => self.process_instr_valuable(v),
-			TopLevelItem::Repetition(r) => self.process_instr_repetition(r),
-			TopLevelItem::Dropper(value) => self.process_instr_dropper(value),
+		match &instruction.kind {
+			TopLevelKind::Definition(d) => self.process_instr_definition(d),
+			TopLevelKind::Valuable(v) => self.process_instr_valuable(v),
+			TopLevelKind::Repetition(r) => self.process_instr_repetition(r),
+			TopLevelKind::While(w) => self.process_instr_while(w),
+			TopLevelKind::ForEach(each) => {
+				self.ops_mut().push(Operations::Each(each));
+				self.ops_mut().push(Operations::Hook(ObjectSymbol::Deck));
+				self.process_instr_valuable(&each.value);
+			},
+			TopLevelKind::Dropper(value) => self.process_instr_dropper(value),
+			TopLevelKind::Break => self.ops_mut().push(Operations::Break(instruction.span)),
+			TopLevelKind::Continue => self.ops_mut().push(Operations::Continue(instruction.span)),
 		}
 	}
 
 	fn process_instr_definition(&mut self, definition: &'rt Definition) {
 		match definition {
-			Definition::Blueprint { name, exprs } => {
+			Definition::Blueprint { name, parameters, parents, exprs } => {
 				self.ops_mut().push(Operations::RegisterBlueprint {
 					ident: name,
+					parameters,
+					parents,
 					expr: exprs,
 				});
 			},
@@ -147,26 +215,44 @@ impl<'rt> ContextStack<'rt> {
 	}
 
 	fn process_instr_valuable(&mut self, value: &'rt Valuable) {
-		match value {
-			Valuable::Expression(expr) => {
+		match &value.kind {
+			ValuableKind::Expression(expr) => {
 				// self.ops_mut().push(Operations::PopContext);
 				self.ops_mut().push(Operations::PushContext(expr));
 			},
-			Valuable::ObjectLiteral(literal) => {
+			ValuableKind::ObjectLiteral(literal) => {
 				self.ops_mut().push(Operations::PushObject(literal));
 			},
-			Valuable::ObjectConversion(obj_conv) => {
+			ValuableKind::ObjectConversion(obj_conv) => {
 				self.ops_mut().push(Operations::ConvertObject(&obj_conv.symbol));
 				self.process_instr_valuable(&obj_conv.value);
 			},
-			Valuable::Identifier(ident) => {
+			ValuableKind::Identifier(ident) => {
 				self.ops_mut().push(Operations::PushIdentifier(ident));
 			},
-			Valuable::Callback(callback) => {
+			ValuableKind::Reference(name) => {
+				self.ops_mut().push(Operations::PushCallable(name, value.span));
+			},
+			ValuableKind::Branch(branch) => {
+				self.ops_mut().push(Operations::Branch(branch));
+				self.ops_mut().push(Operations::Hook(ObjectSymbol::State));
+				self.process_instr_valuable(&branch.cond);
+			},
+			ValuableKind::Callback(callback) if callback.target.is_some() && Logic::from_id(&callback.id).is_some() => {
+				//? The target is evaluated first, arguments are only evaluated if they're needed.
+				self.ops_mut().push(Operations::ShortCircuit {
+					callback,
+					index: 0,
+					span: value.span,
+				});
+				self.process_instr_valuable(callback.target.as_ref().as_ref().unwrap());
+			},
+			ValuableKind::Callback(callback) => {
 				self.ops_mut().push(Operations::ExCallback {
 					method: callback.target.is_some(),
 					callback: &callback.id,
 					parameters: callback.parameters.len(),
+					span: value.span,
 				});
 			
 				if let Some(target) = callback.target.as_ref() {
@@ -192,38 +278,62 @@ impl<'rt> ContextStack<'rt> {
 
 	fn process_instr_repetition(&mut self, rep: &'rt Repetition) {
 		self.ops_mut().push(Operations::Repeat(&rep.value));
+		self.ops_mut().push(Operations::Hook(ObjectSymbol::Count));
 		self.process_instr_valuable(&rep.cond);
 	}
 
+	fn process_instr_while(&mut self, looping: &'rt While) {
+		let table = self.table.len();
+		self.ops_mut().push(Operations::WhileLoop { looping, table });
+	}
+
 	fn push(&mut self, context: ContextHolder<'rt>) {
 		self.stack.push(context);
 		self.at_root = false;
 	}
 
-	fn pop(&mut self) {
+	fn pop(&mut self) -> Result<(), SwearError> {
 		let result = self.table_mut().pop().unwrap_or_default();
 
 		if self.at_root {
+			//? The root context is kept around so more can be appended to it.
 			self.finished = true;
-			self.stack.clear();
-			self.result = Some(std::sync::Arc::into_inner(result.inner()).unwrap().into_inner().unwrap());
-			return;
+			self.result = Some(match std::sync::Arc::try_unwrap(result.inner()) {
+				Ok(lock) => lock.into_inner()?,
+				Err(shared) => shared.read()?.clone(),
+			});
+			return Ok(());
 		}
 
-		if let ContextHolder::RuntimeContext(RuntimeContext::Blueprint(blueprint_cont)) = self.stack.pop().unwrap() {
-			let mut obj = Dynamic::default();
-			for (key, value) in blueprint_cont.items {
-				obj.set(key, value);
-			}
+		match self.stack.pop().unwrap() {
+			ContextHolder::RuntimeContext(RuntimeContext::Blueprint(blueprint_cont)) => {
+				//? The result was never the blueprint's to take, put it back along with anything under it.
+				self.table_mut().push(result);
+				self.table_mut().truncate(blueprint_cont.table);
 
-			self.table_mut().push(ObjectRef::new(obj.into()));
-		} else {
-			self.table_mut().push(result);
-		};
+				let mut obj = Dynamic::default();
+				for (key, value) in blueprint_cont.scope.items()? {
+					obj.set(key, value);
+				}
+
+				self.table_mut().push(ObjectRef::new(obj.into()));
+			},
+			ContextHolder::RuntimeContext(RuntimeContext::Module(module_cont)) => {
+				//? Same goes for a module, the import gets its definitions instead.
+				self.table_mut().push(result);
+				self.table_mut().truncate(module_cont.table);
+
+				let module = self.finish_import(module_cont)?;
+				self.table_mut().push(module);
+			},
+			_ => self.table_mut().push(result),
+		}
 
 		if self.stack.len() == 1 {
 			self.at_root = true;
 		}
+
+		Ok(())
 	}
 
 	// fn cont(&self) -> &ContextHolder {
@@ -252,6 +362,11 @@ impl<'rt> ContextStack<'rt> {
 		}
 	}
 
+	/// The scope names are currently looked up from.
+	fn scope(&self) -> &Scope<'rt> {
+		self.runtime_cont().scope()
+	}
+
 	fn ops(&self) -> &Vec<Operations<'rt>> {
 		&self.runtime_cont().ops()
 	}
@@ -268,21 +383,20 @@ impl<'rt> ContextStack<'rt> {
 		&mut self.table
 	}
 
-	fn table_pop(&mut self) -> ObjectRef<'rt> {
-		match self.table_mut().pop() {
-			Some(obj) => obj,
-			None => {
-				panic!("Table is empty");
-			}
-		}
+	fn table_pop(&mut self) -> Result<ObjectRef<'rt>, SwearError> {
+		self.table_mut().pop().ok_or(SwearError::StackUnderflow)
 	}
 }
 
 impl<'rt> IContext<'rt> for ContextStack<'rt> {
 	fn get(&self,key: &str) -> Option<ContextItem<'rt>> {
 		for context in self.stack.iter().rev() {
-			if let Some(value) = context.get(key) {
-				return Some(value);
+			match context {
+				ContextHolder::ObjectRef(obj) => if let Some(value) = obj.get(key) {
+					return Some(value);
+				},
+				//? A level sees the scopes it's nested in, not whatever happens to be under it on the stack.
+				ContextHolder::RuntimeContext(context) => return context.get(key),
 			}
 		}
 
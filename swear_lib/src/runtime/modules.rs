@@ -0,0 +1,382 @@
+//? Without a parser there is nothing to import, but hosts can still set up the search path.
+#![cfg_attr(not(feature="parser"), allow(dead_code))]
+
+use std::collections::BTreeMap as HashMap;
+use std::path::{Path, PathBuf};
+
+use super::*;
+
+/// The extension Swear scripts are expected to have.
+const EXTENSION: &str = "sw";
+
+/// Where modules and native libraries are looked for, and the ones already loaded.
+///
+/// Shared by a runtime and every module it imports, so each module is only run once
+/// and the host's settings apply to all of them.
+#[derive(Debug)]
+pub struct Modules<'rt> {
+	search_path: Vec<PathBuf>,
+	loaded: HashMap<PathBuf, ObjectRef<'rt>>,
+	/// The modules being run right now, outermost first.
+	loading: Vec<PathBuf>,
+	library_path: Vec<PathBuf>,
+	libraries: HashMap<PathBuf, ObjectRef<'rt>>,
+	libraries_allowed: bool,
+}
+
+impl<'rt> Default for Modules<'rt> {
+	fn default() -> Self {
+		Self {
+			search_path: Vec::new(),
+			loaded: HashMap::new(),
+			loading: Vec::new(),
+			library_path: Vec::new(),
+			libraries: HashMap::new(),
+			libraries_allowed: true,
+		}
+	}
+}
+
+impl<'rt> Modules<'rt> {
+	/// Finds a module in the search path, `name` may leave out the extension.
+	fn resolve(&self, name: &str) -> Option<PathBuf> {
+		let file = Path::new(name);
+		let candidates = [file.to_path_buf(), file.with_extension(EXTENSION)];
+
+		self.search_path.iter()
+			.flat_map(|dir| candidates.iter().map(move |file| dir.join(file)))
+			.find(|path| path.is_file())
+			//? The same file can be reached more than one way, the cache should only see one.
+			.and_then(|path| path.canonicalize().ok())
+	}
+}
+
+impl<'rt> ContextStack<'rt> {
+	/// Adds a directory to look for modules in.
+	/// Directories are searched in the order they were added.
+	pub fn add_module_path(&mut self, path: impl Into<PathBuf>) {
+		if let Ok(mut modules) = self.modules.lock() {
+			modules.search_path.push(path.into());
+		}
+	}
+
+	/// Adds a directory to look for native libraries in, before the one the executable is in.
+	pub fn add_library_path(&mut self, path: impl Into<PathBuf>) {
+		if let Ok(mut modules) = self.modules.lock() {
+			modules.library_path.push(path.into());
+		}
+	}
+
+	/// Sets whether scripts can load native libraries, which they can unless the host says otherwise.
+	/// Libraries that were already loaded stay usable.
+	pub fn allow_libraries(&mut self, allowed: bool) {
+		if let Ok(mut modules) = self.modules.lock() {
+			modules.libraries_allowed = allowed;
+		}
+	}
+
+	/// Loads a native library and returns the Object it set up.
+	/// Like modules, each library is only loaded once.
+	pub(super) fn load_library(&mut self, name: &str, span: Span) -> Result<ObjectRef<'rt>, SwearError> {
+		let mut modules = self.modules.lock()?;
+
+		if !modules.libraries_allowed {
+			return Err(SwearError::LibraryFailed { name: name.into(), reason: "native libraries are turned off".into(), span });
+		}
+
+		let path = crate::dyn_libraries::find_library(name, &modules.library_path)
+			.ok_or_else(|| SwearError::LibraryNotFound { name: name.into(), span })?;
+
+		if let Some(library) = modules.libraries.get(&path) {
+			return Ok(library.copy());
+		}
+
+		let library = crate::dyn_libraries::load_library(&path)
+			.map_err(|e| SwearError::LibraryFailed { name: name.into(), reason: e.to_string(), span })?;
+		modules.libraries.insert(path, library.copy());
+
+		Ok(library)
+	}
+
+	/// Imports another script, leaving its top level definitions on the table as a Dynamic.
+	/// The module runs as a level of its own on the stack, so it's stepped through like anything else.
+	/// A module is only run the first time it's imported, later imports get the same Object.
+	#[cfg(feature="parser")]
+	pub(super) fn import(&mut self, name: &str, span: Span) -> Result<(), SwearError> {
+		use swear_parser::{Location, SwearParser};
+
+		let path = {
+			let modules = self.modules.lock()?;
+			let path = modules.resolve(name).ok_or_else(|| SwearError::ModuleNotFound { name: name.into(), span })?;
+
+			if let Some(module) = modules.loaded.get(&path) {
+				let module = module.copy();
+				drop(modules);
+				self.table_mut().push(module);
+				return Ok(());
+			}
+
+			if let Some(at) = modules.loading.iter().position(|loading| *loading == path) {
+				let chain = modules.loading[at..].iter()
+					.chain(std::iter::once(&path))
+					.map(|path| path.display().to_string())
+					.collect();
+				return Err(SwearError::CyclicImport { chain, span });
+			}
+
+			path
+		};
+
+		let failed = |at: Option<String>, reason: String| SwearError::ModuleFailed { name: name.into(), at, reason: ModuleFailure::Load(reason), span };
+
+		let source = std::fs::read_to_string(&path).map_err(|e| failed(None, e.to_string()))?;
+		let script = SwearParser::new().parse(&source).map_err(|e| {
+			let diagnostic = swear_parser::Diagnostic::from_parse_error(&source, &e);
+			let at = format!("{}:{}", path.display(), Location::from_offset(&source, diagnostic.span.start));
+			failed(Some(at), diagnostic.message)
+		})?;
+
+		//? The runtime borrows every script it runs for as long as it lives,
+		//? modules are only loaded once so leaking them is bounded.
+		let script: &'rt Expression = Box::leak(Box::new(script));
+		let source: &'rt str = source.leak();
+
+		self.modules.lock()?.loading.push(path.clone());
+
+		//? Modules see the host's globals too, but keep their own definitions to themselves.
+		let table = self.table.len();
+		self.push(ModuleContext {
+			scope: self.globals.child(),
+			instructions: script,
+			instr_index: 0,
+			ops: Vec::new(),
+			table,
+			name: name.into(),
+			path,
+			source,
+			span,
+		}.into());
+
+		Ok(())
+	}
+
+	/// Turns a module that has run to the end into the Object its imports get.
+	pub(super) fn finish_import(&mut self, module: ModuleContext<'rt>) -> Result<ObjectRef<'rt>, SwearError> {
+		let mut object = Dynamic::named(module.name);
+		for (key, value) in module.scope.items()? {
+			object.set(key, value);
+		}
+		let object = ObjectRef::new(object.into());
+
+		let mut modules = self.modules.lock()?;
+		modules.loading.retain(|path| *path != module.path);
+		modules.loaded.insert(module.path, object.copy());
+
+		Ok(object)
+	}
+
+	/// Wraps an error in a [`SwearError::ModuleFailed`] for each module it happened in, innermost first.
+	/// Those modules are given up on, importing them again starts them over.
+	pub(super) fn fail_modules(&mut self, mut error: SwearError) -> SwearError {
+		let mut span = error.span().or_else(|| self.current_span());
+		let mut failed = Vec::new();
+
+		for context in self.stack.iter().rev() {
+			let ContextHolder::RuntimeContext(RuntimeContext::Module(module)) = context else {
+				continue;
+			};
+
+			let at = span.map(|span| format!("{}:{}", module.path.display(), span.start_location(module.source)));
+			error = SwearError::ModuleFailed { name: module.name.clone(), at, reason: ModuleFailure::Run(Box::new(error)), span: module.span };
+			span = Some(module.span);
+			failed.push(module.path.clone());
+		}
+
+		if let Ok(mut modules) = self.modules.lock() {
+			modules.loading.retain(|path| !failed.contains(path));
+		}
+
+		error
+	}
+}
+
+#[cfg(all(test, feature="parser"))]
+mod tests {
+	use super::*;
+	use swear_parser::SwearParser;
+
+	/// Writes each module into a directory of the test's own.
+	fn module_dir(test: &str, modules: &[(&str, &str)]) -> PathBuf {
+		let dir = std::env::temp_dir().join(format!("swear_modules_{test}_{}", std::process::id()));
+		std::fs::create_dir_all(&dir).expect("Failed to create the module directory");
+		for (file, source) in modules {
+			std::fs::write(dir.join(file), source).expect("Failed to write a module");
+		}
+		dir
+	}
+
+	fn runtime(script: &str, dir: &Path) -> ContextStack<'static> {
+		let script = SwearParser::new().parse(script).expect("Failed to parse input");
+		let mut runtime = ContextStack::new(Box::leak(Box::new(script)));
+		runtime.add_module_path(dir);
+		runtime
+	}
+
+	fn run(runtime: &mut ContextStack) -> Result<(), SwearError> {
+		while !runtime.is_finished() {
+			runtime.step()?;
+		}
+		Ok(())
+	}
+
+	/// Builds `examples/plugin`, returning the directory the library ends up in.
+	fn build_example_plugin() -> PathBuf {
+		let manifest = Path::new(env!("CARGO_MANIFEST_DIR")).join("../examples/plugin/Cargo.toml");
+		let target = std::env::temp_dir().join("swear_example_plugin");
+
+		//? Its lock file keeps it to the versions this test was built with, which are already fetched.
+		let status = std::process::Command::new(env!("CARGO"))
+			.args(["build", "--quiet", "--offline", "--manifest-path"])
+			.arg(manifest)
+			.arg("--target-dir")
+			.arg(&target)
+			.status()
+			.expect("Failed to run cargo");
+		assert!(status.success(), "Failed to build the example plugin");
+
+		target.join("debug")
+	}
+
+	/// Builds a plugin from a single file of Rust with no dependencies, for plugins that are broken on purpose.
+	/// Returns the directory the library ends up in.
+	fn build_plugin(name: &str, source: &str) -> PathBuf {
+		let dir = std::env::temp_dir().join(format!("swear_plugins_{}", std::process::id()));
+		std::fs::create_dir_all(&dir).expect("Failed to create the plugin directory");
+		let file = dir.join(format!("{name}.rs"));
+		std::fs::write(&file, source).expect("Failed to write the plugin");
+
+		let status = std::process::Command::new(std::env::var_os("RUSTC").unwrap_or("rustc".into()))
+			.args(["--edition", "2021", "--crate-type", "cdylib", "--crate-name", name, "--out-dir"])
+			.arg(&dir)
+			.arg(&file)
+			.status()
+			.expect("Failed to run rustc");
+		assert!(status.success(), "Failed to build the plugin {name}");
+
+		dir
+	}
+
+	/// Test that a module is run once, and every import gets the same Object.
+	#[test]
+	fn test_import_once() {
+		let dir = module_dir("once", &[("counter.sw", "count% '7'#")]);
+		let mut runtime = runtime("a% >import 'counter'$*<\nb% >import 'counter.sw'$*<\na>equals b*<", &dir);
+
+		run(&mut runtime).expect("Failed to run");
+		assert_eq!(runtime.take_result(), Some(Object::from(State::from(true))));
+		let _ = std::fs::remove_dir_all(dir);
+	}
+
+	/// Test that a module that never finishes doesn't hold up the step that imported it.
+	#[test]
+	fn test_import_steps() {
+		let dir = module_dir("steps", &[("forever.sw", "'1'#>equals '1'#*<& [\n\tx% '1'#\n]")]);
+		let mut runtime = runtime(">import 'forever'$*<", &dir);
+
+		for _ in 0..1000 {
+			assert_eq!(runtime.step(), Ok(StepOutcome::Running));
+		}
+		assert!(runtime.stack().iter().any(|cont| matches!(cont, ContextHolder::RuntimeContext(RuntimeContext::Module(_)))));
+		let _ = std::fs::remove_dir_all(dir);
+	}
+
+	/// Test that a host can turn native libraries off, before the library is even looked for.
+	#[test]
+	fn test_libraries_off() {
+		let mut runtime = runtime("'x'$>load<", &std::env::temp_dir());
+		runtime.allow_libraries(false);
+
+		let error = run(&mut runtime).expect_err("Expected the load to fail");
+		let SwearError::LibraryFailed { name, .. } = &error else {
+			panic!("Expected a library failure, found {error:?}");
+		};
+		assert_eq!(name, "x");
+	}
+
+	/// Test that a failing module points at where it failed, and keeps the error it failed with.
+	#[test]
+	fn test_import_error() {
+		let dir = module_dir("error", &[("outer.sw", ">import 'inner'$*<"), ("inner.sw", "x% '1'#\n>nothing<")]);
+		let mut runtime = runtime(">import 'outer'$*<", &dir);
+
+		let error = run(&mut runtime).expect_err("Expected the import to fail");
+		let SwearError::ModuleFailed { name, at, .. } = &error else {
+			panic!("Expected a module failure, found {error:?}");
+		};
+		assert_eq!(name, "outer");
+		assert!(at.as_ref().is_some_and(|at| at.ends_with("outer.sw:1:1")), "{at:?}");
+
+		let inner = std::error::Error::source(&error).expect("Expected the inner module's failure");
+		assert!(inner.to_string().ends_with("inner.sw:2:1"), "{inner}");
+		assert_eq!(inner.source().map(ToString::to_string), Some("callback `nothing` was not found".into()));
+
+		//? The failed modules aren't left half loaded, so they aren't mistaken for a cycle.
+		runtime.append(Box::leak(Box::new(SwearParser::new().parse(">import 'inner'$*<").unwrap())));
+		assert!(matches!(run(&mut runtime), Err(SwearError::ModuleFailed { .. })));
+		let _ = std::fs::remove_dir_all(dir);
+	}
+
+	/// Test that a plugin made with `swear_dyn_api` can be loaded and called,
+	/// and that a panic in it fails the call instead of unwinding into the runtime.
+	#[test]
+	fn test_load_plugin() {
+		let dir = build_example_plugin();
+		let result = |script: &str| {
+			let script = SwearParser::new().parse(script).expect("Failed to parse input");
+			let mut runtime = ContextStack::new(Box::leak(Box::new(script)));
+			runtime.add_library_path(&dir);
+			run(&mut runtime).map(|_| runtime.take_result())
+		};
+		let load = "plugin% 'swear_example_plugin'$>load<\n";
+
+		assert_eq!(result(&format!("{load}plugin>double '21'#*<")), Ok(Some(Object::from(Count::from(42)))));
+		assert_eq!(result(&format!("{load}plugin>shout 'hey'$* '3'#*<")), Ok(Some(Object::from(Chars::from("HEY!!!")))));
+		assert_eq!(result(&format!("{load}counts% ''@\ncounts>set 'a'$* '1'#*<\ncounts>set 'b'$* '2.5'#*<\nplugin>total counts*<")), Ok(Some(Object::from(Count::from(3.5)))));
+
+		let failed = result(&format!("{load}plugin>panic<"));
+		assert!(matches!(failed, Err(SwearError::NativeCallbackFailed { ref name, .. }) if name == "panic"), "{failed:?}");
+
+		//? Arguments that are missing or don't convert fail the call just the same, without reaching the function.
+		for (call, name) in [("plugin>shout 'hey'$* '-1'#*<", "shout"), ("plugin>double<", "double")] {
+			let failed = result(&format!("{load}{call}"));
+			assert!(matches!(failed, Err(SwearError::NativeCallbackFailed { name: ref failed_name, .. }) if failed_name == name), "{call}: {failed:?}");
+		}
+	}
+
+	/// Test that a plugin for another version of the interface, or without an init function, fails to load
+	/// rather than being called into.
+	#[test]
+	fn test_broken_plugins() {
+		use crate::dyn_libraries::abi::ABI_VERSION;
+
+		let load = |name: &str, source: &str| {
+			let dir = build_plugin(name, source);
+			let script = SwearParser::new().parse(&format!("'{name}'$>load<")).expect("Failed to parse input");
+			let mut runtime = ContextStack::new(Box::leak(Box::new(script)));
+			runtime.add_library_path(dir);
+			match run(&mut runtime) {
+				Err(SwearError::LibraryFailed { reason, .. }) => reason,
+				other => panic!("Expected {name} to fail to load, found {other:?}"),
+			}
+		};
+
+		let version = |version: u32| format!("#[no_mangle]\npub extern \"C\" fn swear_plugin_abi_version() -> u32 {{\n\t{version}\n}}\n");
+		let init = "#[no_mangle]\npub extern \"C\" fn swear_plugin_init(_: *const u8, _: *mut u8) -> bool {\n\tpanic!(\"init should not be called\")\n}\n";
+
+		let reason = load("swear_old_plugin", &(version(ABI_VERSION + 1) + init));
+		assert!(reason.contains(&format!("ABI version {}", ABI_VERSION + 1)), "{reason}");
+
+		let reason = load("swear_no_init_plugin", &version(ABI_VERSION));
+		assert!(reason.contains("swear_plugin_init"), "{reason}");
+	}
+}
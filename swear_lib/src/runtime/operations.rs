@@ -1,5 +1,5 @@
 use super::*;
-use swear_parser::{Expression, ObjectLiteral, ObjectSymbol};
+use swear_parser::{Branch, Expression, ForEach, MethodCallback, ObjectLiteral, ObjectSymbol, Span};
 
 use self::Operations::*;
 
@@ -10,7 +10,14 @@ use super::ContextStack;
 pub enum Operations<'rt> {
 	PushObject(&'rt ObjectLiteral),
 	ConvertObject(&'rt ObjectSymbol),
+	/// Converts the Object on the table without going through its conversion callback.
+	Coerce(ObjectSymbol),
+	/// Passes the Object on the table through its conversion callback, if it has one.
+	/// Anything else is left as it is, for whatever uses it to convert.
+	Hook(ObjectSymbol),
 	PushIdentifier(&'rt String),
+	/// Pushes the named callback as a [`Callable`], without calling it.
+	PushCallable(&'rt String, Span),
 	RegisterObject(&'rt String),
 	RegisterCallback {
 		ident: &'rt String,
@@ -19,177 +26,936 @@ pub enum Operations<'rt> {
 	},
 	RegisterBlueprint {
 		ident: &'rt String,
+		parameters: &'rt Vec<String>,
+		parents: &'rt Vec<String>,
 		expr: &'rt Expression,
 	},
+	/// Builds a blueprint that another extends.
+	Instantiate {
+		blueprint: Blueprint<'rt>,
+		args: Vec<ObjectRef<'rt>>,
+	},
+	/// Builds a blueprint once the `parents` it extends have been built, they're on the table.
+	Extend {
+		blueprint: Blueprint<'rt>,
+		args: Vec<ObjectRef<'rt>>,
+		parents: usize,
+	},
 	ExCallback {
 		method: bool,
 		callback: &'rt String,
 		parameters: usize,
+		span: Span,
 	},
+	/// Evaluates a State `and`, `or` or `nand` one operand at a time.
+	/// `index` is the number of arguments already evaluated, the operand on the table is the last of them or the target.
+	ShortCircuit {
+		callback: &'rt MethodCallback,
+		index: usize,
+		span: Span,
+	},
+	PushRef(ObjectRef<'rt>),
+	/// Hands the result of a call scheduled by a native callback back to it.
+	Resume {
+		then: Continuation<'rt>,
+		name: &'rt String,
+		span: Span,
+	},
+	Branch(&'rt Branch),
 	Repeat(&'rt TopLevelItem),
+	/// Runs an item `remaining` more times, one iteration at a time.
+	/// `table` is the height of the table when the loop started, anything above it is dropped when leaving an iteration early.
+	RepeatN {
+		item: &'rt TopLevelItem,
+		remaining: usize,
+		table: usize,
+	},
+	/// Starts another iteration of a while loop, if its condition still holds.
+	WhileLoop {
+		looping: &'rt While,
+		table: usize,
+	},
+	/// Checks the condition of a while loop, which is on the table.
+	WhileCheck(&'rt While),
+	/// Starts a for each over the collection on the table.
+	Each(&'rt ForEach),
+	/// Runs the body of a for each with the next set of bindings, if there are any left.
+	EachNext {
+		each: &'rt ForEach,
+		//? Shared, so the operation stays cheap to clone.
+		items: std::sync::Arc<Vec<Vec<ObjectRef<'rt>>>>,
+		next: usize,
+		table: usize,
+	},
+	Break(Span),
+	Continue(Span),
 	PushContext(&'rt Expression),
 	PopContext,
 }
 
+/// The State callbacks that stop evaluating their arguments once the result is known.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Logic {
+	And,
+	Or,
+	Nand,
+}
+
+impl Logic {
+	pub fn from_id(id: &str) -> Option<Self> {
+		Some(match id {
+			"and" => Logic::And,
+			"or" => Logic::Or,
+			"nand" => Logic::Nand,
+			_ => return None,
+		})
+	}
+
+	/// The result if an operand with this state is found, or None to keep going.
+	fn decide(self, state: bool) -> Option<bool> {
+		match (self, state) {
+			(Logic::And, false) => Some(false),
+			(Logic::Or, true) => Some(true),
+			(Logic::Nand, false) => Some(true),
+			_ => None,
+		}
+	}
+
+	/// The result once every operand has been checked without deciding.
+	fn exhausted(self) -> bool {
+		match self {
+			Logic::And => true,
+			Logic::Or => false,
+			Logic::Nand => false,
+		}
+	}
+}
+
+impl<'rt> Operations<'rt> {
+	/// Whether this operation marks a loop that's in the middle of an iteration.
+	fn is_loop(&self) -> bool {
+		matches!(self, RepeatN { .. } | WhileLoop { .. } | EachNext { .. })
+	}
+}
+
 impl<'rt> ContextStack<'rt> {
+	/// Abandons the rest of the current iteration of the innermost loop.
+	/// When `exit` is set, the loop is abandoned as well.
+	fn unwind_loop(&mut self, exit: bool, span: Span) -> Result<(), SwearError> {
+		let control = if exit { "break" } else { "continue" };
+
+		for depth in (0..self.stack.len()).rev() {
+			let level = match &mut self.stack[depth] {
+				ContextHolder::RuntimeContext(RuntimeContext::ContextLevel(level)) => level,
+				//? Blueprints and modules can only be left by finishing them.
+				ContextHolder::RuntimeContext(RuntimeContext::Blueprint(_) | RuntimeContext::Module(_)) => break,
+				ContextHolder::ObjectRef(_) => continue,
+			};
+
+			if let Some(at) = level.ops.iter().rposition(Operations::is_loop) {
+				let table = match level.ops[at] {
+					RepeatN { table, .. } | WhileLoop { table, .. } | EachNext { table, .. } => table,
+					_ => unreachable!(),
+				};
+
+				level.ops.truncate(if exit { at } else { at + 1 });
+				self.stack.truncate(depth + 1);
+				self.at_root = self.stack.len() == 1;
+				self.table.truncate(table);
+				return Ok(());
+			}
+
+			if level.call {
+				break;
+			}
+		}
+
+		Err(SwearError::OutsideLoop { control, span })
+	}
+
+	/// Runs a Swear callback with the given arguments.
+	/// Missing arguments are Zip, and extra ones are dropped.
+	fn invoke_swear(&mut self, callback: SwearCallback<'rt>, target: Option<ObjectRef<'rt>>, args: Vec<ObjectRef<'rt>>) -> Result<(), SwearError> {
+		let scope = callback.scope.child_of(target.clone());
+		if let Some(target) = target {
+			scope.bind("self".into(), target.into())?;
+		}
+
+		let mut args = args.into_iter();
+		for name in callback.args {
+			scope.bind(name, args.next().unwrap_or_default().into())?;
+		}
+
+		self.push(ContextLevel::new_call(callback.callback, scope).into());
+		Ok(())
+	}
+
+	/// Calls the conversion callback of the Object on top of the table, if it has one for `symbol`.
+	/// Only Swear callbacks count, so only Dynamics built from blueprints can have them.
+	fn convert_hook(&mut self, symbol: ObjectSymbol) -> Result<(), SwearError> {
+		let name = match symbol {
+			ObjectSymbol::Chars => "to_chars",
+			ObjectSymbol::Count => "to_count",
+			ObjectSymbol::State => "to_state",
+			ObjectSymbol::Deck => "to_deck",
+			ObjectSymbol::Map => "to_map",
+			ObjectSymbol::Zip => return Ok(()),
+		};
+
+		let callback = match self.table.last() {
+			Some(obj) => obj.try_access()?.as_dynamic().and_then(|obj| obj.hook(name)),
+			None => return Err(SwearError::StackUnderflow),
+		};
+
+		if let Some(callback) = callback {
+			let target = self.table_pop()?;
+			self.invoke_swear(callback, Some(target), Vec::new())?;
+		}
+
+		Ok(())
+	}
+
+	/// Builds an instance of a blueprint, passing the arguments to its constructor.
+	/// Like a callback, missing arguments are Zip, and extra ones are dropped.
+	///
+	/// The blueprints it extends are built first, with the same arguments.
+	fn instantiate(&mut self, blueprint: Blueprint<'rt>, args: Vec<ObjectRef<'rt>>) -> Result<(), SwearError> {
+		if blueprint.parents.is_empty() {
+			self.construct(blueprint, args, Vec::new())?;
+			return Ok(());
+		}
+
+		//? Parents are looked up where the blueprint was defined, like any other name in it.
+		let parents = blueprint.parents.iter().map(|name| match blueprint.scope.get(name) {
+			Some(ContextItem::Blueprint(parent)) => Ok(parent),
+			other => Err(SwearError::TypeMismatch {
+				expected: format!("a blueprint named `{name}` to extend"),
+				found: match other {
+					Some(ContextItem::Object(obj)) => obj.access().get_info().name,
+					Some(ContextItem::Callback(_)) => "a callback".into(),
+					_ => "nothing".into(),
+				},
+			}),
+		}).collect::<Result<Vec<_>, _>>()?;
+
+		self.ops_mut().push(Extend { blueprint, args: args.clone(), parents: parents.len() });
+		for parent in parents.into_iter().rev() {
+			self.ops_mut().push(Instantiate { blueprint: parent, args: args.clone() });
+		}
+
+		Ok(())
+	}
+
+	/// Runs the body of a blueprint, on top of the contents of its already built parents.
+	fn construct(&mut self, blueprint: Blueprint<'rt>, args: Vec<ObjectRef<'rt>>, parents: Vec<ObjectRef<'rt>>) -> Result<(), SwearError> {
+		//? Arguments get a scope of their own, so they aren't mistaken for fields.
+		let scope = blueprint.scope.child();
+
+		let mut args = args.into_iter();
+		for name in blueprint.args {
+			scope.bind(name, args.next().unwrap_or_default().into())?;
+		}
+
+		let body = scope.child();
+		if !parents.is_empty() {
+			let mut prototype = Dynamic::prototype();
+			for parent in parents {
+				let Object::Dynamic(parent) = parent.access().clone() else {
+					continue;
+				};
+
+				//? Later parents win over earlier ones, and the body wins over all of them.
+				for (key, value) in parent {
+					body.bind(key.clone(), value.clone())?;
+					prototype.set(key, value);
+				}
+			}
+
+			scope.bind("super".into(), ObjectRef::new(prototype.into()).into())?;
+		}
+
+		let table = self.table.len();
+		self.push(BlueprintContext::new(blueprint.expr, body, table).into());
+		Ok(())
+	}
+
+	fn get_blueprint(&self, name: &str) -> Option<Blueprint<'rt>> {
+		match self.get(name)? {
+			ContextItem::Blueprint(blueprint) => Some(blueprint),
+			_ => None,
+		}
+	}
+
+	/// Finds a blueprint in the Dynamic on top of the table.
+	fn get_member_blueprint(&self, name: &str) -> Option<Blueprint<'rt>> {
+		match &*self.table.last()?.access() {
+			Object::Dynamic(obj) => match obj.get(name)? {
+				ContextItem::Blueprint(blueprint) => Some(blueprint),
+				_ => None,
+			},
+			_ => None,
+		}
+	}
+
+	/// Takes the arguments of a call off the table.
+	fn table_pop_args(&mut self, count: usize) -> Result<Vec<ObjectRef<'rt>>, SwearError> {
+		let mut args = Vec::with_capacity(count);
+		for _ in 0..count {
+			args.push(self.table_pop()?);
+		}
+
+		Ok(args)
+	}
+
+	/// Finds the callback a name refers to, either directly or through a [`Callable`] registered under it.
+	fn get_callback(&self, name: &str) -> Option<Callback<'rt>> {
+		match self.get(name)? {
+			ContextItem::Callback(callback) => Some(callback),
+			ContextItem::Object(obj) => match &*obj.access() {
+				Object::Callable(callable) => Some(callable.callback.clone()),
+				_ => None,
+			},
+			_ => None,
+		}
+	}
+
+	/// Calls a callback given as an Object, on behalf of a native callback.
+	/// The Object is either a [`Callable`], or the name of a callback as Chars.
+	/// With a target, the callback runs as a method of it.
+	fn invoke(&mut self, callback: ObjectRef<'rt>, target: Option<ObjectRef<'rt>>, args: Vec<ObjectRef<'rt>>, span: Span) -> Result<(), SwearError> {
+		let (name, callback) = match &*callback.try_access()? {
+			Object::Callable(callable) => (callable.name.clone(), Some(callable.callback.clone())),
+			Object::Chars(chars) => (chars.chars.clone(), self.get_callback(&chars.chars)),
+			other => return Err(SwearError::TypeMismatch {
+				expected: "a callback".into(),
+				found: other.get_info().name,
+			}),
+		};
+
+		match callback {
+			Some(Callback::Swear(callback)) => {
+				self.invoke_swear(callback, target, args)
+			},
+			Some(Callback::Native(_)) => Err(SwearError::TypeMismatch {
+				expected: format!("a method target for native callback `{name}`"),
+				found: "a call from a native callback".into(),
+			}),
+			None => Err(SwearError::UnknownCallback { name, span }),
+		}
+	}
+
+	/// Calls a native callback, with Zip as the target of a bare call.
+	fn call_native(&mut self, callback: NativeCallback<'rt>, obj: Option<ObjectRef<'rt>>, args: Vec<ObjectRef<'rt>>, name: &'rt String, span: Span) -> Result<(), SwearError> {
+		let obj = obj.unwrap_or_else(|| Object::from(Zip).into());
+
+		let invoker = Invoker::default();
+		let result = callback.callback
+			.lock()?
+			.call_mut((obj, args, invoker.clone()));
+
+		self.finish_native(result, invoker, name, span)
+	}
+
+	/// Puts the result of a native callback on the table,
+	/// or starts the call it scheduled and waits for that instead.
+	fn finish_native(&mut self, result: NativeResult<'rt>, invoker: Invoker<'rt>, name: &'rt String, span: Span) -> Result<(), SwearError> {
+		let result = result.map_err(|_| SwearError::NativeCallbackFailed { name: name.clone(), span })?;
+
+		match invoker.take() {
+			Some(invocation) => {
+				self.ops_mut().push(Resume { then: invocation.then, name, span });
+				self.invoke(invocation.callback, invocation.target, invocation.args, span)
+			},
+			None => {
+				self.table_mut().push(result.unwrap_or_default());
+				Ok(())
+			},
+		}
+	}
+
 	/// Handles the next operation in the stack.
 	/// 
 	/// # Panics
 	/// 
 	/// Panics if the operation stack is empty.
-	pub(super) fn handle_next_op(&mut self) {
+	pub(super) fn handle_next_op(&mut self) -> Result<(), SwearError> {
 		let operation = self.ops_mut().pop().unwrap();
-		self.handle_op(operation);
+		self.handle_op(operation)
 	}
 
-	pub(super) fn handle_op(&mut self, op: Operations<'rt>) {
-		'op_match: {
-			match op {
-				PushObject(object) => {
-					self.table_mut().push(Object::from_literal(object).into());
-				},
-				ConvertObject(symbol) => {
-					let object = self.table_mut().pop().expect("FIXME");
-					let object = object.access();
-					self.table_mut().push(match symbol {
-						ObjectSymbol::Chars => Object::from(object.to_chars()).into(),
-						ObjectSymbol::Count => Object::from(object.to_count()).into(),
-						ObjectSymbol::State => Object::from(object.to_state()).into(),
-						ObjectSymbol::Zip => Object::from(object.to_zip()).into(),
-						ObjectSymbol::Deck => Object::from(object.to_deck()).into(),
-						ObjectSymbol::Map => Object::from(object.to_map()).into(),
-					});
-				},
-				PushIdentifier(ident) => {
-					let item = self.get(&ident);
-					match item {
-						Some(ContextItem::Object(obj)) => {
-							let obj = obj.copy();
-							self.table_mut().push(obj);
-						},
-						Some(ContextItem::Callback(callback)) => {
-							match callback {
-								Callback::Native(NativeCallback { arg_count: _, callback: _ }) => {
-									// let mut args = Vec::with_capacity(arg_count);
-									// for _ in 0..arg_count {
-									// 	args.push(Object::default().into()); //FIXME: Arg count.
-									// }
-			
-									// let result = callback.lock().unwrap().call_mut((None, args)).ok().flatten().unwrap_or_default(); //TODO: Error handling.
-									// self.table_mut().push(result);
-									unreachable!("Called non-method native function"); //? Swear does not have native functions.
-								},
-								Callback::Swear(SwearCallback { args, callback }) => {
-									self.push(ContextLevel::new(&callback).into());
-									for arg in args.into_iter().rev() {
-										self.set(arg, ObjectRef::default().into());
-									}
-								}
-							}
-						},
-						Some(ContextItem::Blueprint(blueprint)) => {
-							self.push(BlueprintContext::new(blueprint.expr).into());
-						},
-						None => self.table_mut().push(Object::default().into()),
-					}
-					
-				},
-				ExCallback { method, callback: id, parameters } => {
-					let (obj, Some(callback)) = (if method {
-						let objref = self.table_pop();
-						let obj = objref.access();
-						let func = obj.get_function(id);
-						drop(obj);
-						(Some(objref), func.map(|func| func.function.clone())) //TODO: Clone?
-					} else {
-						match self.get(&id) {
-							Some(ContextItem::Callback(callback)) => (None, Some(callback.clone())), //TODO: Clone?
-							_ => (None, None),
+	pub(super) fn handle_op(&mut self, op: Operations<'rt>) -> Result<(), SwearError> {
+		match op {
+			PushObject(object) => {
+				self.table_mut().push(Object::from_literal(object).into());
+			},
+			ConvertObject(symbol) => {
+				self.ops_mut().push(Coerce(*symbol));
+				self.convert_hook(*symbol)?;
+			},
+			Hook(symbol) => self.convert_hook(symbol)?,
+			Coerce(symbol) => {
+				let object = self.table_pop()?;
+				let object = object.try_access()?;
+				self.table_mut().push(match symbol {
+					ObjectSymbol::Chars => Object::from(object.to_chars()).into(),
+					ObjectSymbol::Count => Object::from(object.to_count()).into(),
+					ObjectSymbol::State => Object::from(object.to_state()).into(),
+					ObjectSymbol::Zip => Object::from(object.to_zip()).into(),
+					ObjectSymbol::Deck => Object::from(object.to_deck()).into(),
+					ObjectSymbol::Map => Object::from(object.to_map()).into(),
+				});
+			},
+			PushIdentifier(ident) => {
+				let item = self.get(&ident);
+				match item {
+					Some(ContextItem::Object(obj)) => {
+						let obj = obj.copy();
+						self.table_mut().push(obj);
+					},
+					Some(ContextItem::Callback(callback)) => {
+						match callback {
+							Callback::Native(callback) => {
+								let span = self.current_span().unwrap_or_default();
+								self.call_native(callback, None, Vec::new(), ident, span)?;
+							},
+							Callback::Swear(callback) => self.invoke_swear(callback, None, Vec::new())?,
 						}
-					}) else {
-						println!("Function not found: {}", id);
-						self.table_mut().push(Object::default().into());
-						break 'op_match;
-					};
-
-					match callback {
-						Callback::Native(callback) => {
-							let mut args = Vec::with_capacity(parameters);
-							for _ in 0..parameters {
-								args.push(self.table_pop());
-							}
-
-							let result = callback.callback
-								.lock()
-								.unwrap()
-								.call_mut((obj.expect("Called non-method native function"), args))
-								.ok()
-								.flatten()
-								.unwrap_or_default(); //TODO: Error handling.
-
-							self.table_mut().push(result);
-						},
-						Callback::Swear(callback) => {
-							self.push(ContextLevel::new(&callback.callback).into());
-							if let Some(obj) = obj {
-								self.push(obj.into());
-							}
-							
-							let diff = parameters as i128 - callback.args.len() as i128; //? i128 to prevent underflow.
-							if diff > 0 {
-								for _ in 0..=diff {
-									self.table_mut().pop();
-								}
-							} else if diff < 0 {
-								for _ in 0..=diff {
-									self.table_mut().push(Object::default().into());
-								}
-							}
-							
-							for arg in callback.args.into_iter() {
-								let obj = self.table_pop();
-								self.set(arg, obj.into());
-							}
+					},
+					Some(ContextItem::Blueprint(blueprint)) => {
+						self.instantiate(blueprint, Vec::new())?;
+					},
+					None => self.table_mut().push(Object::default().into()),
+				}
+				
+			},
+			PushCallable(name, span) => {
+				let callable = match self.get(name) {
+					Some(ContextItem::Callback(callback)) => Callable::new(name.clone(), callback),
+					Some(ContextItem::Object(obj)) => match &*obj.try_access()? {
+						Object::Callable(callable) => callable.clone(),
+						other => return Err(SwearError::TypeMismatch {
+							expected: format!("a callback named `{name}`"),
+							found: other.get_info().name,
+						}),
+					},
+					_ => return Err(SwearError::UnknownCallback { name: name.clone(), span }),
+				};
+
+				self.table_mut().push(Object::from(callable).into());
+			},
+			//? Calling a blueprint by name builds an instance of it.
+			ExCallback { method: false, callback: id, parameters, .. } if self.get_blueprint(id).is_some() => {
+				let blueprint = self.get_blueprint(id).unwrap();
+				let args = self.table_pop_args(parameters)?;
+				self.instantiate(blueprint, args)?;
+			},
+			Instantiate { ref blueprint, ref args } => {
+				self.instantiate(blueprint.clone(), args.clone())?;
+			},
+			Extend { ref blueprint, ref args, parents } => {
+				let mut built = self.table_pop_args(parents)?;
+				built.reverse();
+				self.construct(blueprint.clone(), args.clone(), built)?;
+			},
+			//? Blueprints in a Dynamic, like those of a module, are built the same way.
+			ExCallback { method: true, callback: id, parameters, .. } if self.get_member_blueprint(id).is_some() => {
+				let blueprint = self.get_member_blueprint(id).unwrap();
+				self.table_pop()?;
+				let args = self.table_pop_args(parameters)?;
+				self.instantiate(blueprint, args)?;
+			},
+			//? `import` is only built in for as long as nothing else has taken the name.
+			#[cfg(feature="parser")]
+			ExCallback { method: false, callback: id, parameters, span } if id == "import" && self.get(id).is_none() => {
+				let args = self.table_pop_args(parameters)?;
+				let name = match args.first() {
+					Some(name) => name.try_access()?.to_chars().chars,
+					None => String::new(),
+				};
+				self.import(&name, span)?;
+			},
+			//? Chars have no `load` of their own, so this doesn't shadow anything.
+			ExCallback { method: true, callback: id, parameters, span } if id == "load" && self.table.last().is_some_and(|obj| obj.try_access().is_ok_and(|obj| obj.is_chars())) => {
+				let name = self.table_pop()?.try_access()?.to_chars().chars;
+				self.table_pop_args(parameters)?;
+				let library = self.load_library(&name, span)?;
+				self.table_mut().push(library);
+			},
+			ExCallback { method, callback: id, parameters, span } => {
+				let (obj, callback) = if method {
+					let objref = self.table_pop()?;
+					let obj = objref.try_access()?;
+					let func = obj.get_function(id);
+					drop(obj);
+					(Some(objref), func.map(|func| func.function.clone())) //TODO: Clone?
+				} else {
+					(None, self.get_callback(id))
+				};
+
+				let Some(callback) = callback else {
+					return Err(SwearError::UnknownCallback { name: id.clone(), span });
+				};
+
+				let args = self.table_pop_args(parameters)?;
+
+				match callback {
+					Callback::Native(callback) => self.call_native(callback, obj, args, id, span)?,
+					Callback::Swear(callback) => {
+						//? A Callable's `call` runs its callback as if it were called by name.
+						let obj = obj.filter(|obj| !(id == "call" && obj.access().as_callable().is_some()));
+						//? Methods called through `super` still run on the instance.
+						let obj = match (obj, self.get("self")) {
+							(Some(obj), Some(ContextItem::Object(instance))) if obj.access().is_prototype() => Some(instance),
+							(obj, _) => obj,
+						};
+						self.invoke_swear(callback, obj, args)?;
+					},
+				}
+			},
+			Resume { ref then, name, span } => {
+				let result = self.table_pop()?;
+				let invoker = Invoker::default();
+				let result = then.resume(result, invoker.clone()).unwrap_or(Err(()));
+
+				self.finish_native(result, invoker, name, span)?;
+			},
+			ShortCircuit { callback, index, span } => {
+				let logic = Logic::from_id(&callback.id).unwrap();
+				let operand = self.table_pop()?;
+				let state = match &*operand.try_access()? {
+					Object::State(state) => Some(state.state),
+					//? Other targets might have their own version, so call it as normal.
+					_ if index == 0 => None,
+					obj => Some(obj.to_state().state),
+				};
+
+				match state.map(|state| (logic.decide(state), callback.parameters.get(index))) {
+					None => {
+						self.ops_mut().push(ExCallback {
+							method: true,
+							callback: &callback.id,
+							parameters: callback.parameters.len(),
+							span,
+						});
+						self.ops_mut().push(PushRef(operand));
+						for param in callback.parameters.iter() {
+							self.process_instr_valuable(param);
 						}
+					},
+					Some((Some(result), _)) => self.table_mut().push(Object::from(State::from(result)).into()),
+					Some((None, None)) => self.table_mut().push(Object::from(State::from(logic.exhausted())).into()),
+					Some((None, Some(next))) => {
+						self.ops_mut().push(ShortCircuit { callback, index: index + 1, span });
+						self.process_instr_valuable(next);
+					},
+				}
+			},
+			Branch(branch) => {
+				let cond = self.table_pop()?;
+				let cond = cond.try_access()?.to_state().state;
+
+				//? Only the chosen arm is ever evaluated.
+				let arm = match cond {
+					true => &branch.then,
+					false => &branch.otherwise,
+				};
+				self.push(ContextLevel::new(arm, self.scope().child()).into());
+			},
+			PushRef(ref obj) => {
+				self.table_mut().push(obj.copy());
+			},
+			Repeat(instr) => {
+				let cond = self.table_pop()?;
+				let cond = cond.try_access()?;
+				let count = match &*cond {
+					Object::State(state) => if state.state { 1 } else { 0 },
+					Object::Count(count) => count.to_usize(),
+					obj => obj.to_count().to_usize(),
+				};
+
+				drop(cond);
+				let table = self.table.len();
+				self.ops_mut().push(RepeatN { item: instr, remaining: count, table });
+			},
+			RepeatN { item, remaining, table } => {
+				if remaining > 0 {
+					self.ops_mut().push(RepeatN { item, remaining: remaining - 1, table });
+					self.process_instructions(item);
+				}
+			},
+			WhileLoop { looping, table } => {
+				self.ops_mut().push(WhileCheck(looping));
+				self.ops_mut().push(Hook(ObjectSymbol::State));
+				self.table.truncate(table);
+				self.process_instr_valuable(&looping.cond);
+			},
+			WhileCheck(looping) => {
+				let cond = self.table_pop()?;
+				let cond = cond.try_access()?.to_state().state;
+
+				if cond {
+					let table = self.table.len();
+					self.ops_mut().push(WhileLoop { looping, table });
+					self.process_instructions(&looping.value);
+				}
+			},
+			Each(each) => {
+				let value = self.table_pop()?;
+				let value = value.try_access()?;
+
+				let items: Vec<_> = match (&*value, each.names.len()) {
+					(Object::Map(map), 2..) => map.map.iter().map(|(k, v)| vec![k.snapshot(), v.copy()]).collect(),
+					(obj, 2..) => obj.to_deck().deck.into_iter().enumerate().map(|(i, item)| vec![Object::from(Count::from(i)).into(), item]).collect(),
+					(obj, _) => obj.to_deck().deck.into_iter().map(|item| vec![item]).collect(),
+				};
+
+				drop(value);
+				let table = self.table.len();
+				self.ops_mut().push(EachNext { each, items: items.into(), next: 0, table });
+			},
+			EachNext { each, ref items, next, table } => {
+				self.table.truncate(table);
+
+				if let Some(bound) = items.get(next) {
+					let bound = bound.clone();
+					self.ops_mut().push(EachNext { each, items: items.clone(), next: next + 1, table });
+					self.push(ContextLevel::new(&each.body, self.scope().child()).into());
+
+					//? The names belong to the loop, even where they'd match a field of `self`.
+					let mut bound = bound.into_iter();
+					for name in each.names.iter() {
+						self.scope().bind(name.clone(), bound.next().unwrap_or_default().into())?;
 					}
-				},
-				Repeat(instr) => {
-					let cond = self.table_pop();
-					let cond = cond.access();
-					let count = match &*cond {
-						Object::State(state) => if state.state { 1 } else { 0 },
-						Object::Count(count) => count.count as usize,
-						obj => obj.to_count().count as usize,
-					};
-
-					for _ in 0..count {
-						self.process_instructions(instr);
-					}
-				},
-				RegisterObject(ident) => {
-					let obj = self.table_pop();
-					self.set(ident.clone(), obj.into()); //TODO: Clone
-				},
-				RegisterCallback { ident, parameters, expr } => {
-					self.set(ident.clone(), ContextItem::Callback(Callback::Swear(SwearCallback { //TODO: Clone
-						args: parameters.clone(), //TODO: Clone
-						callback: expr,
-					})));
-				},
-				RegisterBlueprint { ident, expr } => {
-					self.set(ident.clone(), ContextItem::Blueprint(Blueprint {
-						expr,
-					}));
-				},
-				PushContext(instructions) => {
-					self.push(ContextLevel::new(instructions).into());
-				},
-				PopContext => {
-					self.pop();
-				},
-			}
+				}
+			},
+			Break(span) => self.unwind_loop(true, span)?,
+			Continue(span) => self.unwind_loop(false, span)?,
+			RegisterObject(ident) => {
+				let obj = self.table_pop()?;
+				self.scope().define(ident.clone(), obj.into())?; //TODO: Clone
+			},
+			RegisterCallback { ident, parameters, expr } => {
+				self.scope().define(ident.clone(), ContextItem::Callback(Callback::Swear(SwearCallback { //TODO: Clone
+					args: parameters.clone(), //TODO: Clone
+					callback: expr,
+					scope: self.scope().clone(),
+				})))?;
+			},
+			RegisterBlueprint { ident, parameters, parents, expr } => {
+				self.scope().define(ident.clone(), ContextItem::Blueprint(Blueprint {
+					args: parameters.clone(),
+					parents: parents.clone(),
+					expr,
+					scope: self.scope().clone(),
+				}))?;
+			},
+			PushContext(instructions) => {
+				self.push(ContextLevel::new(instructions, self.scope().child()).into());
+			},
+			PopContext => {
+				self.pop()?;
+			},
 		}
 
 		self.last_op = Some(op);
+		Ok(())
+	}
+}
+
+#[cfg(all(test, feature="parser"))]
+mod tests {
+	use super::*;
+	use swear_parser::SwearParser;
+
+	/// Defines `yes` and `no`, since there's no State literal.
+	const STATES: &str = "yes% '1'#>equals '1'#*<\nno% '1'#>equals '0'#*<\n";
+
+	/// Runs a script to the end, returning what it left on the table.
+	fn run(script: &str) -> Result<Option<Object<'static>>, SwearError> {
+		let script = SwearParser::new().parse(&format!("{STATES}{script}")).expect("Failed to parse input");
+		let mut runtime = ContextStack::new(Box::leak(Box::new(script)));
+		while !runtime.is_finished() {
+			runtime.step()?;
+		}
+		Ok(runtime.take_result())
+	}
+
+	fn state(state: bool) -> Option<Object<'static>> {
+		Some(Object::from(State::from(state)))
+	}
+
+	fn count(count: i64) -> Option<Object<'static>> {
+		Some(Object::from(Count::from(count)))
+	}
+
+	fn chars(chars: &str) -> Option<Object<'static>> {
+		Some(Object::from(Chars::from(chars)))
+	}
+
+	/// Test that `and`, `or` and `nand` stop at the first operand that decides them,
+	/// so nothing after it is evaluated.
+	#[test]
+	fn test_short_circuit() {
+		assert_eq!(run("no>and >missing<*<"), Ok(state(false)));
+		assert_eq!(run("yes>or >missing<*<"), Ok(state(true)));
+		assert_eq!(run("no>nand >missing<*<"), Ok(state(true)));
+		assert_eq!(run("yes>and yes* no* >missing<*<"), Ok(state(false)));
+
+		assert!(matches!(run("yes>and >missing<*<"), Err(SwearError::UnknownCallback { .. })));
+		assert!(matches!(run("no>or no* >missing<*<"), Err(SwearError::UnknownCallback { .. })));
+	}
+
+	/// Test the results when no operand decides them.
+	#[test]
+	fn test_undecided() {
+		assert_eq!(run("yes>and yes* yes*<"), Ok(state(true)));
+		assert_eq!(run("no>or no* no*<"), Ok(state(false)));
+		assert_eq!(run("yes>nand yes*<"), Ok(state(false)));
+		assert_eq!(run("yes>and<"), Ok(state(true)));
+	}
+
+	#[test]
+	fn test_state_logic() {
+		assert_eq!(run("yes>xor yes* yes*<"), Ok(state(true)));
+		assert_eq!(run("yes>xor yes*<"), Ok(state(false)));
+		assert_eq!(run("no>xor no* yes*<"), Ok(state(true)));
+		assert_eq!(run("yes>not<"), Ok(state(false)));
+		assert_eq!(run("no>not<"), Ok(state(true)));
+		assert_eq!(run("no>equals no* no*<"), Ok(state(true)));
+		assert_eq!(run("yes>equals yes* no*<"), Ok(state(false)));
+	}
+
+	/// Test that a branch runs exactly one arm, and yields what that arm left.
+	#[test]
+	fn test_branch() {
+		assert_eq!(run("yes? ['a'$] ['b'$]"), Ok(chars("a")));
+		assert_eq!(run("no? ['a'$] ['b'$]"), Ok(chars("b")));
+		assert_eq!(run("n% '0'#\nyes? [n>add '1'#*<] [n>add '10'#*<]\nn"), Ok(count(1)));
+		assert_eq!(run("n% '0'#\nno? [n>add '1'#*<] [n>add '10'#*<]\nn"), Ok(count(10)));
+		assert_eq!(run("pick! c* [\n\tc? ['a'$] ['b'$]\n]\n>pick no*<"), Ok(chars("b")));
+	}
+
+	/// Test that a condition that isn't a State is read as one, and an empty arm yields Zip.
+	#[test]
+	fn test_branch_coercion() {
+		assert_eq!(run("'x'$? ['a'$] ['b'$]"), Ok(chars("a")));
+		assert_eq!(run("''$? ['a'$] ['b'$]"), Ok(chars("b")));
+		assert_eq!(run("'1 2'|? ['a'$] ['b'$]"), Ok(chars("a")));
+		assert_eq!(run("''|? ['a'$] ['b'$]"), Ok(chars("b")));
+		assert_eq!(run("nothing% no? ['a'$] []\nnothing>solid<"), Ok(state(false)));
+	}
+
+	/// Test that a while loop checks its condition before every iteration, including the first.
+	#[test]
+	fn test_while() {
+		assert_eq!(run("i% '0'#\ni>less '5'#*<& i>add '1'#*<\ni"), Ok(count(5)));
+		assert_eq!(run("i% '0'#\ni>greater '0'#*<& i>add '1'#*<\ni"), Ok(count(0)));
+	}
+
+	/// Test that `\` skips the rest of an iteration and `/` leaves the loop, in both kinds of loop.
+	#[test]
+	fn test_break_continue() {
+		let looped = |head: &str| run(&format!("j% '0'#\ntotal% '0'#\n{head} [\n\tj>add '1'#*<\n\tj>equals '3'#*<? [\\] []\n\tj>greater '5'#*<? [/] []\n\ttotal>add j*<\n]\ntotal"));
+
+		assert_eq!(looped("yes&"), Ok(count(1 + 2 + 4 + 5)));
+		assert_eq!(looped("'10'#+"), Ok(count(1 + 2 + 4 + 5)));
+		assert_eq!(looped("'4'#+"), Ok(count(1 + 2 + 4)));
+	}
+
+	/// Test that a repeat runs one iteration at a time, rather than laying out every iteration up front.
+	#[test]
+	fn test_repeat_lazy() {
+		assert_eq!(run("i% '0'#\n'1e15'#+ [\n\ti>add '1'#*<\n\t/\n]\ni"), Ok(count(1)));
+		assert_eq!(run("i% '0'#\nno+ i>add '1'#*<\ni"), Ok(count(0)));
+	}
+
+	/// Test that `/` and `\` don't reach a loop outside the callback they're in.
+	#[test]
+	fn test_outside_loop() {
+		assert!(matches!(run("/"), Err(SwearError::OutsideLoop { .. })));
+		assert!(matches!(run("skip! [ \\ ]\n'1'#+ >skip<"), Err(SwearError::OutsideLoop { .. })));
+	}
+
+	/// Test that a for each binds every item in order, or every index and item with two names.
+	#[test]
+	fn test_each_deck() {
+		assert_eq!(run("total% '0'#\n'1 2 3'|= x* [\n\ttotal>add x*<\n]\ntotal"), Ok(count(6)));
+		assert_eq!(
+			run("seen% ''|\n'a b c'|= i* item* [\n\tseen>push i* item*<\n]\nseen>join ' '$*<"),
+			Ok(Some(Object::from(Chars::from("0 a 1 b 2 c")))),
+		);
+	}
+
+	/// Test that a Map binds each key and value with two names, and each `[key, value]` pair with one.
+	#[test]
+	fn test_each_map() {
+		let scores = "scores% ''@\nscores>set 'b'$* '2'#*<\nscores>set 'a'$* '1'#*<\nseen% ''|\n";
+
+		assert_eq!(
+			run(&format!("{scores}scores= name* score* [\n\tseen>push name* score*<\n]\nseen>join ' '$*<")),
+			Ok(Some(Object::from(Chars::from("a 1 b 2")))),
+		);
+		assert_eq!(
+			run(&format!("{scores}scores= pair* [\n\tseen>push pair>get '-1'#*<*<\n]\nseen>join ' '$*<")),
+			Ok(Some(Object::from(Chars::from("1 2")))),
+		);
+	}
+
+	/// Test that the items are read once up front, so changing the collection doesn't change the loop.
+	#[test]
+	fn test_each_snapshot() {
+		assert_eq!(run("items% '1 2'|\nn% '0'#\nitems= x* [\n\titems>push x*<\n\tn>add '1'#*<\n]\nn"), Ok(count(2)));
+	}
+
+	/// Test that the names are only bound inside the loop, and that `/` leaves it early.
+	#[test]
+	fn test_each_scope() {
+		assert_eq!(run("x% 'outer'$\n'1 2'|= x* []\nx"), Ok(Some(Object::from(Chars::from("outer")))));
+		assert_eq!(run("n% '0'#\n'1 2 3 4'|= x* [\n\tx>equals '3'#*<? [/] []\n\tn>add x*<\n]\nn"), Ok(count(3)));
+	}
+
+	const DOUBLE: &str = "double! n* [\n\tn#>mul '2'#*<\n]\n";
+
+	/// Test that a Callable can be stored, passed and returned, and called by name or with `call`.
+	#[test]
+	fn test_callables() {
+		assert_eq!(run(&format!("{DOUBLE}f% !double\n>f '4'#*<")), Ok(count(8)));
+		assert_eq!(run(&format!("{DOUBLE}f% !double\nf>call '4'#*<")), Ok(count(8)));
+		assert_eq!(run(&format!("{DOUBLE}apply! g* x* [\n\t>g x*<\n]\n>apply !double* '5'#*<")), Ok(count(10)));
+		assert_eq!(run(&format!("{DOUBLE}make! [\n\t!double\n]\nh% >make<\n>h '3'#*<")), Ok(count(6)));
+		assert_eq!(run(&format!("{DOUBLE}fs% ''|\nfs>push !double*<\ng% fs>get '0'#*<\n>g '2'#*<")), Ok(count(4)));
+	}
+
+	/// Test that Callables are equal only when they hold the same callback, count their arguments, and can't refer to unknown names.
+	#[test]
+	fn test_callable_identity() {
+		assert_eq!(run(&format!("{DOUBLE}a% !double\nb% !double\na>equals b*<")), Ok(state(true)));
+		assert_eq!(run(&format!("{DOUBLE}a% !double\ndouble! n* [n]\nb% !double\na>equals b*<")), Ok(state(false)));
+		assert_eq!(run(&format!("{DOUBLE}f% !double\nf#")), Ok(count(1)));
+		assert!(matches!(run("f% !nothing"), Err(SwearError::UnknownCallback { .. })));
+	}
+
+	/// Test that a callback keeps the scope it was defined in, even after that scope is done with.
+	#[test]
+	fn test_closures() {
+		assert_eq!(run("make! [\n\tsecret% '7'#\n\tget! [secret]\n\t!get\n]\ng% >make<\n>g<"), Ok(count(7)));
+
+		let counter = "counter! start* [\n\tn% start#\n\tnext! [n>add '1'#*<]\n\t!next\n]\na% >counter '0'#*<\nb% >counter '10'#*<\n";
+		assert_eq!(run(&format!("{counter}>a<\n>a<\n>a<")), Ok(count(3)));
+		assert_eq!(run(&format!("{counter}>a<\n>a<\n>b<")), Ok(count(11)));
+	}
+
+	/// Test that names are looked up where a callback was written, not where it's called from,
+	/// and that what a callback defines stays inside it.
+	#[test]
+	fn test_lexical_scope() {
+		assert_eq!(run("show! [x]\nx% 'global'$\ncaller! [\n\tx% 'local'$\n\t>show<\n]\n>caller<"), Ok(chars("global")));
+		assert_eq!(run("x% 'outer'$\nf! [x% 'inner'$]\n>f<\nx"), Ok(chars("outer")));
+		assert!(matches!(run("f! [y% '1'#]\n>f<\n>y<"), Err(SwearError::UnknownCallback { .. })));
+		assert!(matches!(run("f! [>hidden<]\ng! [\n\thidden! ['x'$]\n\t>f<\n]\n>g<"), Err(SwearError::UnknownCallback { .. })));
+	}
+
+	const POINT: &str = "Point: px* py* [\n\tx% px\n\ty% py\n\tsum! [\n\t\tx#>add y*<<\n\t]\n\tgrow! by* [\n\t\tx% x#>add by*<\n\t\tself<\n\t]\n]\n";
+
+	/// Test that a blueprint's parameters are bound in its body, and missing ones are Zip.
+	#[test]
+	fn test_blueprint_params() {
+		assert_eq!(run(&format!("{POINT}p% >Point '3'#* '4'#*<\np>sum<")), Ok(count(7)));
+		assert_eq!(run(&format!("{POINT}p% >Point '3'#*<\np>get 'y'$*<")), Ok(Some(Object::from(Zip))));
+
+		//? Parameters are only there to build the fields from, they aren't fields themselves.
+		assert_eq!(run(&format!("{POINT}p% >Point '3'#* '4'#*<\np>get 'px'$*<")), Ok(Some(Object::from(Zip))));
+	}
+
+	/// Test that methods see their instance as `self`, and setting a field in one changes the instance.
+	#[test]
+	fn test_blueprint_self() {
+		assert_eq!(run(&format!("{POINT}p% >Point '3'#* '4'#*<\np>grow '2'#*<>sum<")), Ok(count(9)));
+		assert_eq!(run(&format!("{POINT}p% >Point '3'#* '4'#*<\np>grow '2'#*<\np>get 'x'$*<")), Ok(count(5)));
+
+		//? Each instance has fields of its own.
+		assert_eq!(run(&format!("{POINT}p% >Point '3'#* '4'#*<\nq% >Point '1'#* '1'#*<\nq>grow '5'#*<\np>sum<")), Ok(count(7)));
+	}
+
+	/// Test that fields can be set with `set`, from outside the instance or through `self`.
+	#[test]
+	fn test_blueprint_set() {
+		assert_eq!(run(&format!("{POINT}p% >Point '3'#* '4'#*<\np>set 'x'$* '100'#*<\np>sum<")), Ok(count(104)));
+
+		let reset = "Counter: [\n\tn% '5'#\n\treset! [\n\t\tself>set 'n'$* '0'#*<\n\t]\n]\nc% >Counter<\nc>reset<\nc>get 'n'$*<";
+		assert_eq!(run(reset), Ok(count(0)));
+	}
+
+	/// Test that `%` in a method sets a field the instance already has, at the top of the method or nested in it,
+	/// and that everything else stays local to the method.
+	#[test]
+	fn test_method_locals() {
+		let boxed = "Box: [\n\tx% '1'#\n\tset_x! [\n\t\tscratch% '5'#\n\t\tx% scratch\n\t]\n\tset_nested! [\n\t\tscratch% '7'#\n\t\tyes? [\n\t\t\tx% scratch\n\t\t\tinner% '3'#\n\t\t] []\n\t]\n\tloop! [\n\t\t'1 2'|= x* []\n\t]\n]\nb% >Box<\n";
+
+		assert_eq!(run(&format!("{boxed}b>set_x<\nb>get 'x'$*<")), Ok(count(5)));
+		assert_eq!(run(&format!("{boxed}b>set_x<\nb>get 'scratch'$*<")), Ok(Some(Object::from(Zip))));
+
+		assert_eq!(run(&format!("{boxed}b>set_nested<\nb>get 'x'$*<")), Ok(count(7)));
+		assert_eq!(run(&format!("{boxed}b>set_nested<\nb>get 'scratch'$*<")), Ok(Some(Object::from(Zip))));
+		assert_eq!(run(&format!("{boxed}b>set_nested<\nb>get 'inner'$*<")), Ok(Some(Object::from(Zip))));
+
+		//? A loop's names are its own, even when a field shares one.
+		assert_eq!(run(&format!("{boxed}b>loop<\nb>get 'x'$*<")), Ok(count(1)));
+	}
+
+	const ENEMIES: &str = "Entity: name* hp* [
+	label% name
+	health% hp
+	describe! [
+		''$>concat label* ' has '$* health$*<<
+	]
+	hurt! n* [
+		health% health#>sub n*<
+		self<
+	]
+]
+Named: [
+	title! [
+		''$>concat 'the '$* label*<<
+	]
+]
+Enemy: Entity: Named: name* hp* [
+	health% health#>mul '2'#*<
+	describe! [
+		''$>concat super>describe<* ' and is angry'$*<<
+	]
+]
+Boss: Enemy: name* hp* [
+	describe! [
+		''$>concat 'BOSS: '$* super>describe<*<<
+	]
+]
+";
+
+	/// Test that a blueprint gets the fields and methods of every parent, and can build on them in its body.
+	#[test]
+	fn test_inheritance() {
+		assert_eq!(run(&format!("{ENEMIES}>Enemy 'orc'$* '10'#*<>get 'health'$*<")), Ok(count(20)));
+		assert_eq!(run(&format!("{ENEMIES}>Enemy 'orc'$* '10'#*<>title<")), Ok(chars("the orc")));
+		assert_eq!(run(&format!("{ENEMIES}>Enemy 'orc'$* '10'#*<>hurt '5'#*<>get 'health'$*<")), Ok(count(15)));
+
+		//? Parents are left as they were.
+		assert_eq!(run(&format!("{ENEMIES}>Enemy 'orc'$* '10'#*<\n>Entity 'rat'$* '1'#*<>describe<")), Ok(chars("rat has 1")));
+	}
+
+	/// Test that `super` reaches the method that was overridden, running it on the instance, through any number of levels.
+	#[test]
+	fn test_super() {
+		assert_eq!(run(&format!("{ENEMIES}>Enemy 'orc'$* '10'#*<>describe<")), Ok(chars("orc has 20 and is angry")));
+		assert_eq!(run(&format!("{ENEMIES}>Enemy 'orc'$* '10'#*<>hurt '5'#*<>describe<")), Ok(chars("orc has 15 and is angry")));
+		assert_eq!(run(&format!("{ENEMIES}>Boss 'dragon'$* '50'#*<>describe<")), Ok(chars("BOSS: dragon has 100 and is angry")));
+	}
+
+	/// Test that later parents win over earlier ones, and extending a blueprint that doesn't exist fails.
+	#[test]
+	fn test_parents() {
+		assert_eq!(run("A: [\n\tv% '1'#\n]\nB: [\n\tv% '2'#\n]\nC: A: B: []\n>C<>get 'v'$*<"), Ok(count(2)));
+		assert_eq!(run("A: [\n\tv% '1'#\n]\nB: [\n\tv% '2'#\n]\nC: B: A: []\n>C<>get 'v'$*<"), Ok(count(1)));
+		assert!(matches!(run("Bad: Nope: []\n>Bad<"), Err(SwearError::TypeMismatch { .. })));
 	}
 }
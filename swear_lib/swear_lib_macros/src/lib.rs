@@ -13,8 +13,16 @@ pub fn swear_object(_: TokenStream, item: TokenStream) -> TokenStream {
     swear_object_macro::swear_object(input).into()
 }
 
+/// Turns an `impl` block into a plugin for `swear_lib::dyn_libraries`.
+///
+/// Every function in the block is added to the plugin's Object under its own name.
+/// Arguments are converted with `FromValue` and results with `IntoValue`,
+/// so a function that can fail should return a `Result` or take an `Option`.
+/// A function that panics fails its call, rather than unwinding into the host.
+///
+/// `examples/plugin` is a whole plugin built with it.
 #[proc_macro_attribute]
-pub fn sewar_dyn_api(_: TokenStream, item: TokenStream) -> TokenStream {
+pub fn swear_dyn_api(_: TokenStream, item: TokenStream) -> TokenStream {
 	let input = parse_macro_input!(item as ItemImpl);
 	swear_dyn_api_macro::swear_dyn_api(input).into()
 }
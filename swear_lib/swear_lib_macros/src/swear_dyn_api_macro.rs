@@ -1,31 +1,91 @@
 use super::*;
 use proc_macro2::TokenStream;
-use quote::ToTokens;
-use syn::{parse::Parse, parse_quote, punctuated::Punctuated, FnArg, Ident, ImplItem, PatType, Type};
+use quote::format_ident;
+use syn::{spanned::Spanned, FnArg, ImplItem, LitStr};
 
-pub fn swear_dyn_api(mut input: ItemImpl) -> TokenStream {
-	let name = input.self_ty.clone();
+pub fn swear_dyn_api(input: ItemImpl) -> TokenStream {
+	let self_ty = &input.self_ty;
 
-	input.items = input.items.iter().cloned().map(|item| {
-		let ImplItem::Fn(mut func) = item else {
-			return item;
+	if let Some(param) = input.generics.params.first() {
+		return syn::Error::new(param.span(), "a plugin can't be generic").to_compile_error();
+	}
+
+	let mut wrappers = Vec::new();
+	let mut registrations = Vec::new();
+
+	for item in &input.items {
+		let ImplItem::Fn(func) = item else {
+			continue;
 		};
+		let sig = &func.sig;
 
-		let inputs = &func.sig.inputs.clone();
-		// func.sig.variadic
-		func.sig.inputs.clear();
-		if let Some(FnArg::Typed(PatType { ty, pat, .. })) = inputs.first() && ty.into_token_stream().to_string() == "Self" {
-			func.sig.inputs = parse_quote!(#pat: ObjectRef);
-		} else {
-			func.sig.inputs = parse_quote!(_: ObjectRef);
+		if let Some(receiver) = sig.receiver() {
+			return syn::Error::new(receiver.span(), "plugin functions are called without an instance, they can't take `self`").to_compile_error();
+		}
+		if let Some(param) = sig.generics.params.first() {
+			return syn::Error::new(param.span(), "plugin functions can't be generic").to_compile_error();
 		}
 
-		func.into()
-	}).collect();
+		let name = &sig.ident;
+		let wrapper = format_ident!("__swear_plugin_{}", name);
+		let exported = LitStr::new(&name.to_string(), name.span());
+
+		// Each argument is converted from the handle at the same position, a missing one fails the call.
+		let args = sig.inputs.iter().enumerate().map(|(index, arg)| {
+			let FnArg::Typed(arg) = arg else { unreachable!() };
+			let ty = &arg.ty;
+			quote! {
+				match args.get::<#ty>(#index) {
+					Some(arg) => arg,
+					None => return ::std::ptr::null_mut(),
+				}
+			}
+		});
+
+		wrappers.push(quote! {
+			unsafe extern "C" fn #wrapper(
+				_: *mut ::std::ffi::c_void,
+				host: *const SwearHost,
+				_: *const SwearValue,
+				args: *const *const SwearValue,
+				arg_count: usize,
+			) -> *mut SwearValue {
+				// A panic can't unwind into the host, so it fails the call instead.
+				let call = ::std::panic::AssertUnwindSafe(|| {
+					let host = unsafe { &*host };
+					let args = unsafe { Args::new(host, args, arg_count) };
+					IntoValue::into_value(<#self_ty>::#name(#(#args),*), host)
+				});
+				::std::panic::catch_unwind(call).unwrap_or(::std::ptr::null_mut())
+			}
+		});
 
-	let output = quote! {
+		registrations.push(quote! {
+			unsafe { (host.register)(plugin, SwearStr::new(#exported), #wrapper, ::std::ptr::null_mut(), None) };
+		});
+	}
+
+	quote! {
 		#input
-	};
 
-	output
+		const _: () = {
+			use ::swear_lib::dyn_libraries::{abi::*, plugin::*};
+
+			#[unsafe(no_mangle)]
+			pub extern "C" fn swear_plugin_abi_version() -> u32 {
+				ABI_VERSION
+			}
+
+			#(#wrappers)*
+
+			#[unsafe(no_mangle)]
+			pub unsafe extern "C" fn swear_plugin_init(host: *const SwearHost, plugin: *mut SwearPlugin) -> bool {
+				let init = ::std::panic::AssertUnwindSafe(|| {
+					let host = unsafe { &*host };
+					#(#registrations)*
+				});
+				::std::panic::catch_unwind(init).is_ok()
+			}
+		};
+	}
 }
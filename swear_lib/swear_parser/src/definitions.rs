@@ -5,6 +5,10 @@ use super::*;
 pub enum Definition {
 	Blueprint {
 		name: String,
+		/// Passed when the blueprint is built, visible to its body and the methods defined in it.
+		parameters: Vec<String>,
+		/// The blueprints this one extends, in the order their contents are inherited.
+		parents: Vec<String>,
 		exprs: Expression,
 	},
 	Callback {
@@ -19,9 +23,11 @@ pub enum Definition {
 }
 
 impl Definition {
-	pub fn new_blueprint(name: &str, contents: Expression) -> Self {
+	pub fn new_blueprint(name: &str, parameters: Vec<&str>, parents: Vec<&str>, contents: Expression) -> Self {
 		Definition::Blueprint {
 			name: name.into(),
+			parameters: parameters.iter().map(|s| s.to_string()).collect(),
+			parents: parents.iter().map(|s| s.to_string()).collect(),
 			exprs: contents,
 		}
 	}
@@ -0,0 +1,351 @@
+use std::fmt::Display;
+
+use lalrpop_util::lexer::Token;
+
+use super::*;
+
+/// The error type produced by [`SwearParser`].
+pub type ParseError<'input> = lalrpop_util::ParseError<usize, Token<'input>, &'static str>;
+
+/// A readable description of something wrong with a script.
+///
+/// Diagnostics don't hold on to the source they were made from;
+/// pass it back in through [`Diagnostic::report`] to render the snippet.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Diagnostic {
+	pub message: String,
+	pub span: Span,
+	/// Shown next to the carets under the offending source.
+	pub label: Option<String>,
+	pub help: Option<String>,
+}
+
+impl Diagnostic {
+	pub fn new(message: impl Into<String>, span: Span) -> Self {
+		Self {
+			message: message.into(),
+			span,
+			label: None,
+			help: None,
+		}
+	}
+
+	pub fn with_label(mut self, label: impl Into<String>) -> Self {
+		self.label = Some(label.into());
+		self
+	}
+
+	pub fn with_help(mut self, help: impl Into<String>) -> Self {
+		self.help = Some(help.into());
+		self
+	}
+
+	/// Turns a raw parser error into something a person can act on.
+	pub fn from_parse_error(source: &str, error: &ParseError) -> Self {
+		match error {
+			ParseError::InvalidToken { location } => invalid_token(source, *location),
+			ParseError::UnrecognizedEof { location, expected } => {
+				//? Point just past the last thing written, rather than at trailing blank lines.
+				let end = source[..*location].trim_end().len();
+				unexpected(source, Span::point(end), None, expected)
+			},
+			ParseError::UnrecognizedToken { token: (start, Token(_, text), end), expected } =>
+				unexpected(source, Span::new(*start, *end), Some(text), expected),
+			ParseError::ExtraToken { token: (start, Token(_, text), end) } =>
+				Diagnostic::new(format!("unexpected {}", describe_found(text)), Span::new(*start, *end)),
+			ParseError::User { error } => Diagnostic::new(*error, Span::point(0)),
+		}
+	}
+
+	/// Renders this diagnostic against the source it came from.
+	/// `origin` names the source, usually a file path.
+	pub fn report<'a>(&'a self, source: &'a str, origin: &'a str) -> Report<'a> {
+		Report {
+			diagnostic: self,
+			source,
+			origin,
+		}
+	}
+}
+
+impl Display for Diagnostic {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		write!(f, "{}", self.message)
+	}
+}
+
+impl std::error::Error for Diagnostic {}
+
+/// A [`Diagnostic`] paired with its source, ready to be printed.
+///
+/// ```text
+/// error: expected `<` to close callback `fib`
+///  --> script.sw:1:9
+///   |
+/// 1 | >fib n#*
+///   |         ^ callback `fib` is still open here
+/// ```
+pub struct Report<'a> {
+	diagnostic: &'a Diagnostic,
+	source: &'a str,
+	origin: &'a str,
+}
+
+impl Display for Report<'_> {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		let Report { diagnostic, source, origin } = self;
+		let start = diagnostic.span.start_location(source);
+
+		let line = source.lines().nth(start.line - 1).unwrap_or_default();
+		let gutter = " ".repeat(start.line.to_string().len());
+
+		//? Spans that run past the end of the line are cut short, the first line is enough to go on.
+		let width = diagnostic.span.slice(source)
+			.lines()
+			.next()
+			.map(|s| s.chars().count())
+			.unwrap_or(0)
+			.max(1);
+
+		writeln!(f, "error: {}", diagnostic.message)?;
+		writeln!(f, "{gutter}--> {origin}:{start}")?;
+		writeln!(f, "{gutter} |")?;
+		writeln!(f, "{} | {}", start.line, line.replace('\t', " "))?;
+		write!(f, "{gutter} | {}{}", " ".repeat(start.column - 1), "^".repeat(width))?;
+		if let Some(label) = &diagnostic.label {
+			write!(f, " {label}")?;
+		}
+		writeln!(f)?;
+		if let Some(help) = &diagnostic.help {
+			writeln!(f, "{gutter} = help: {help}")?;
+		}
+
+		Ok(())
+	}
+}
+
+fn invalid_token(source: &str, location: usize) -> Diagnostic {
+	let rest = source.get(location..).unwrap_or_default();
+	match rest.chars().next() {
+		Some('\'') => {
+			let line_end = rest.find('\n').unwrap_or(rest.len());
+			Diagnostic::new("unterminated `'` literal", Span::new(location, location + line_end))
+				.with_label("this literal is never closed")
+				.with_help("close it with another `'`, quotes inside a literal are written as `~'`")
+		},
+		Some(c) => Diagnostic::new(format!("unexpected character `{c}`"), Span::new(location, location + c.len_utf8()))
+			.with_label("Swear doesn't use this symbol"),
+		None => Diagnostic::new("unexpected end of input", Span::point(location)),
+	}
+}
+
+fn unexpected(source: &str, span: Span, found: Option<&str>, expected: &[String]) -> Diagnostic {
+	let expects = |name: &str| expected.iter().any(|e| e == name);
+	let open = OpenItems::scan(source, span.start);
+	let found_desc = found.map(describe_found).unwrap_or_else(|| "end of input".into());
+
+	// A literal missing its closing `'` swallows everything up to the next one,
+	// which usually shows up as nonsense a line or two later.
+	if let Some(start) = open.multiline_literal {
+		let line_end = source[start..].find('\n').map(|i| start + i).unwrap_or(source.len());
+		return Diagnostic::new("unterminated `'` literal", Span::new(start, line_end))
+			.with_label("this literal runs onto the next line")
+			.with_help("close it with another `'`, quotes inside a literal are written as `~'`");
+	}
+
+	// A name in a parameter list that was never given its `*`.
+	if expected.len() == 1 && expects("PARAMETER") {
+		let before = source[..span.start].trim_end_matches(is_ident_char);
+		let name = &source[before.len()..span.start];
+		return Diagnostic::new(format!("expected `*` after parameter `{name}`"), Span::new(before.len(), span.start))
+			.with_label("add a `*` here")
+			.with_help("every parameter is followed by `*`, as in `fib! n* [ ... ]`");
+	}
+
+	if found == Some("]") && open.expressions.is_empty() {
+		return Diagnostic::new("unmatched `]`", span)
+			.with_label("there is no `[` for this to close");
+	}
+
+	// Callbacks are closed before expressions, so check the innermost open item first.
+	if expects("DROPPER") && let Some((name, opened)) = open.callbacks.last()
+		&& open.expressions.last().is_none_or(|bracket| bracket < opened) {
+		let label = match found {
+			Some(_) => format!("found {found_desc}"),
+			None => format!("callback `{name}` is still open here"),
+		};
+		return Diagnostic::new(format!("expected `<` to close callback `{name}`"), span)
+			.with_label(label)
+			.with_help("arguments are followed by `*`, and the call is closed with `<`, as in `>fib n*<`");
+	}
+
+	if expects("EXPRES_END") && let Some(opened) = open.expressions.last() {
+		let at = Span::point(*opened).start_location(source);
+		return Diagnostic::new(format!("expected `]` to close the expression opened at {at}"), span)
+			.with_label(format!("found {found_desc}"));
+	}
+
+	if expected.len() == 1 && expects("WS") {
+		return Diagnostic::new(format!("expected whitespace before {found_desc}"), span)
+			.with_label("items must be separated by whitespace");
+	}
+
+	let mut names: Vec<&str> = expected.iter()
+		.filter(|e| *e != "WS")
+		.map(|e| describe_expected(e))
+		.collect();
+	names.dedup();
+
+	let message = match names.as_slice() {
+		[] => format!("unexpected {found_desc}"),
+		[one] => format!("expected {one}, found {found_desc}"),
+		[init @ .., last] => format!("expected {} or {last}, found {found_desc}", init.join(", ")),
+	};
+
+	Diagnostic::new(message, span)
+}
+
+/// A human name for a terminal in the grammar.
+fn describe_expected(terminal: &str) -> &str {
+	match terminal {
+		"WS" => "whitespace",
+		"IDENT" => "a name",
+		"LITERAL" => "a `'literal'`",
+		"EXPRES_START" => "`[`",
+		"EXPRES_END" => "`]`",
+		"REPEAT" => "`+`",
+		"BRANCH" => "`?`",
+		"WHILE" => "`&`",
+		"EACH" => "`=`",
+		"BREAK" => "`/`",
+		"CONTINUE" => "`\\`",
+		"BLUEPRINT" => "`:`",
+		"REGISTER" => "`%`",
+		"CALLBACK" => "`!`",
+		"PARAMETER" => "`*`",
+		"ACCESSOR" => "`>`",
+		"DROPPER" => "`<`",
+		"CHARS" => "`$`",
+		"COUNT" => "`#`",
+		"STATE" => "`^`",
+		"ZIP" => "`~`",
+		"DECK" => "`|`",
+		"MAP" => "`@`",
+		other => other,
+	}
+}
+
+fn describe_found(text: &str) -> String {
+	if text.trim().is_empty() {
+		"whitespace".into()
+	} else {
+		format!("`{text}`")
+	}
+}
+
+fn is_ident_char(c: char) -> bool {
+	c.is_ascii_alphanumeric() || c == '_'
+}
+
+/// Callbacks and expressions left open at some point in a script.
+///
+/// This is a rough lexical scan, not a parse; it only has to be right
+/// often enough to name the thing a person forgot to close.
+#[derive(Debug, Default)]
+struct OpenItems<'s> {
+	/// Open `>name` calls, with the offset of their `>`.
+	callbacks: Vec<(&'s str, usize)>,
+	/// Offsets of open `[`s.
+	expressions: Vec<usize>,
+	/// The offset of the last literal that spans more than one line.
+	multiline_literal: Option<usize>,
+}
+
+impl<'s> OpenItems<'s> {
+	fn scan(source: &'s str, upto: usize) -> Self {
+		let mut open = OpenItems::default();
+		let text = &source[..upto.min(source.len())];
+		let mut chars = text.char_indices().peekable();
+
+		while let Some((i, c)) = chars.next() {
+			match c {
+				'\'' => {
+					// Skip the literal, minding `~` escapes.
+					while let Some((_, c)) = chars.next() {
+						match c {
+							'~' => { chars.next(); },
+							'\'' => break,
+							'\n' => open.multiline_literal = Some(i),
+							_ => {},
+						}
+					}
+				},
+				';' => {
+					while chars.next_if(|(_, c)| *c != '\n' && *c != ';').is_some() {}
+				},
+				'[' => open.expressions.push(i),
+				']' => {
+					if let Some(bracket) = open.expressions.pop() {
+						// Calls opened inside the expression can't outlive it.
+						open.callbacks.retain(|(_, at)| *at < bracket);
+					}
+				},
+				'>' => {
+					let rest = &text[i + 1..];
+					let len = rest.find(|c| !is_ident_char(c)).unwrap_or(rest.len());
+					open.callbacks.push((&rest[..len], i));
+				},
+				'<' => {
+					// Otherwise it's a dropper.
+					if let Some((_, at)) = open.callbacks.last()
+						&& open.expressions.last().is_none_or(|bracket| bracket < at) {
+						open.callbacks.pop();
+					}
+				},
+				_ => {},
+			}
+		}
+
+		open
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn diagnose(source: &str) -> Diagnostic {
+		let error = SwearParser::new().parse(source).expect_err("Expected the script to fail to parse");
+		Diagnostic::from_parse_error(source, &error)
+	}
+
+	#[test]
+	fn test_unclosed_callback() {
+		let diagnostic = diagnose("x% 'a'$\n>fib n#*");
+		assert_eq!(diagnostic.message, "expected `<` to close callback `fib`");
+
+		let report = diagnostic.report("x% 'a'$\n>fib n#*", "test.sw").to_string();
+		assert!(report.contains("--> test.sw:2:9"), "{report}");
+		assert!(report.contains("2 | >fib n#*"), "{report}");
+	}
+
+	#[test]
+	fn test_missing_parameter_sigil() {
+		let diagnostic = diagnose("fib! n [ n< ]");
+		assert_eq!(diagnostic.message, "expected `*` after parameter `n`");
+		assert_eq!(diagnostic.span, Span::new(5, 6));
+	}
+
+	#[test]
+	fn test_unterminated_literal() {
+		let diagnostic = diagnose("x% 'abc$\ny% '1'#");
+		assert_eq!(diagnostic.message, "unterminated `'` literal");
+		assert_eq!(diagnostic.span, Span::new(3, 8));
+	}
+
+	#[test]
+	fn test_unclosed_expression() {
+		let diagnostic = diagnose("fib! n* [\n\tn<");
+		assert_eq!(diagnostic.message, "expected `]` to close the expression opened at 1:9");
+	}
+}
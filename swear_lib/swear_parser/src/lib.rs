@@ -1,5 +1,8 @@
 mod definitions;
 mod valuables;
+mod span;
+#[cfg(feature="parser")]
+mod diagnostics;
 
 // #[cfg(feature="serde")]
 // mod serde_sup;
@@ -10,36 +13,81 @@ lalrpop_mod!(grammar);
 #[cfg(feature="parser")]
 use lalrpop_util::lalrpop_mod;
 
+use std::ops::Deref;
+
 pub use valuables::*;
 pub use definitions::*;
+pub use span::*;
 
 #[cfg(feature="parser")]
 pub use grammar::fileParser as SwearParser;
+#[cfg(feature="parser")]
+pub use diagnostics::*;
+
+/// A single item in an [`Expression`], along with where it came from.
+#[cfg_attr(feature="serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Debug, Clone)]
+pub struct TopLevelItem {
+	pub kind: TopLevelKind,
+	pub span: Span,
+}
+
+impl TopLevelItem {
+	pub fn new(kind: impl Into<TopLevelKind>, span: Span) -> Self {
+		Self { kind: kind.into(), span }
+	}
+}
+
+impl Deref for TopLevelItem {
+	type Target = TopLevelKind;
+
+	fn deref(&self) -> &Self::Target {
+		&self.kind
+	}
+}
 
 #[cfg_attr(feature="serde", derive(serde::Serialize, serde::Deserialize))]
 #[derive(Debug, Clone)]
-pub enum TopLevelItem {
+pub enum TopLevelKind {
 	Valuable(Valuable),
 	Definition(Definition),
 	Repetition(Box<Repetition>),
+	While(Box<While>),
+	ForEach(Box<ForEach>),
 	Dropper(Option<Valuable>),
+	/// Leaves the innermost loop.
+	Break,
+	/// Skips to the next iteration of the innermost loop.
+	Continue,
 }
 
-impl From<Valuable> for TopLevelItem {
+impl From<Valuable> for TopLevelKind {
 	fn from(value: Valuable) -> Self {
-		TopLevelItem::Valuable(value)
+		TopLevelKind::Valuable(value)
 	}
 }
 
-impl From<Definition> for TopLevelItem {
+impl From<Definition> for TopLevelKind {
 	fn from(value: Definition) -> Self {
-		TopLevelItem::Definition(value)
+		TopLevelKind::Definition(value)
 	}
 }
 
-impl From<Repetition> for TopLevelItem {
+impl From<Repetition> for TopLevelKind {
 	fn from(value: Repetition) -> Self {
-		TopLevelItem::Repetition(Box::new(value))
+		TopLevelKind::Repetition(Box::new(value))
+	}
+}
+
+impl From<While> for TopLevelKind {
+	fn from(value: While) -> Self {
+		TopLevelKind::While(Box::new(value))
+	}
+}
+
+impl From<ForEach> for TopLevelKind {
+	fn from(value: ForEach) -> Self {
+		TopLevelKind::ForEach(Box::new(value))
 	}
 }
 
@@ -56,12 +104,206 @@ impl Repetition {
 	}
 }
 
+/// Runs an item for as long as a condition holds.
+/// Unlike [`Repetition`], the condition is evaluated again before every iteration.
+#[cfg_attr(feature="serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Debug, Clone)]
+pub struct While {
+	pub cond: Valuable,
+	pub value: TopLevelItem,
+}
+
+impl While {
+	pub fn new(cond: Valuable, value: TopLevelItem) -> Self {
+		Self { cond, value, }
+	}
+}
+
+/// Runs an expression once for every item in a collection.
+///
+/// Written as `value= name* [body]`. With one name, each item is bound to it.
+/// With two, a Map binds each key and value, and anything else binds each index and item.
+#[cfg_attr(feature="serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Debug, Clone)]
+pub struct ForEach {
+	pub value: Valuable,
+	pub names: Vec<String>,
+	pub body: Expression,
+}
+
+impl ForEach {
+	pub fn new(value: Valuable, names: Vec<&str>, body: Expression) -> Self {
+		Self {
+			value,
+			names: names.into_iter().map(String::from).collect(),
+			body,
+		}
+	}
+}
+
 #[cfg(test)]
 mod tests {
 	use super::*;
 
 	const TEST_SCRIPT: &str = include_str!("../../../testing_script.sw");
 
+	/// Collects the span of every top level item, depth first.
+	fn spans(expr: &Expression) -> Vec<Span> {
+		let mut result = Vec::new();
+		for item in expr.iter() {
+			result.push(item.span);
+			match &item.kind {
+				TopLevelKind::Definition(Definition::Blueprint { exprs, .. }) |
+				TopLevelKind::Definition(Definition::Callback { exprs, .. }) => result.extend(spans(exprs)),
+				_ => {},
+			}
+		}
+		result
+	}
+
+	/// Test that spans point back at the text each node was parsed from.
+	#[test]
+	fn test_spans() {
+		let input = "fib! n* [\n\ta% >fib n#>sub '2'#*<*<\n]\n>fib '3'#*<";
+
+		let parser = SwearParser::new();
+		let ast = parser.parse(input).expect("Failed to parse input");
+
+		assert_eq!(ast.len(), 2);
+		assert_eq!(ast[0].span.start_location(input), Location { line: 1, column: 1 });
+		assert_eq!(ast[1].span.slice(input), ">fib '3'#*<");
+		assert_eq!(ast[1].span.start_location(input), Location { line: 4, column: 1 });
+
+		let TopLevelKind::Definition(Definition::Callback { exprs, .. }) = &ast[0].kind else {
+			panic!("Expected a callback definition");
+		};
+		let TopLevelKind::Definition(Definition::Register { value, .. }) = &exprs[0].kind else {
+			panic!("Expected a register definition");
+		};
+		assert_eq!(value.span.slice(input), ">fib n#>sub '2'#*<*<");
+		assert_eq!(value.span.start_location(input), Location { line: 2, column: 5 });
+
+		let ValuableKind::Callback(callback) = &value.kind else {
+			panic!("Expected a callback");
+		};
+		assert_eq!(callback.parameters[0].span.slice(input), "n#>sub '2'#*<");
+	}
+
+	/// Test that a branch keeps both of its arms.
+	#[test]
+	fn test_branch() {
+		let input = "x% n>less '2'#*<? [\n\t'small'$<\n] ['big'$<]";
+
+		let parser = SwearParser::new();
+		let ast = parser.parse(input).expect("Failed to parse input");
+
+		let TopLevelKind::Definition(Definition::Register { value, .. }) = &ast[0].kind else {
+			panic!("Expected a register definition");
+		};
+		let ValuableKind::Branch(branch) = &value.kind else {
+			panic!("Expected a branch");
+		};
+		assert_eq!(branch.cond.span.slice(input), "n>less '2'#*<");
+		assert_eq!(branch.then.len(), 1);
+		assert_eq!(branch.otherwise.len(), 1);
+
+		assert!(parser.parse("x? ['a'$<]").is_err(), "A branch needs both arms");
+	}
+
+	/// Test that loops and their droppers parse.
+	#[test]
+	fn test_while() {
+		let input = "i>less '10'#*<& [\n\ti>equals '5'#*<? [/] [\\]\n]";
+
+		let parser = SwearParser::new();
+		let ast = parser.parse(input).expect("Failed to parse input");
+
+		let TopLevelKind::While(looping) = &ast[0].kind else {
+			panic!("Expected a while loop");
+		};
+		assert_eq!(looping.cond.span.slice(input), "i>less '10'#*<");
+
+		let TopLevelKind::Valuable(body) = &looping.value.kind else {
+			panic!("Expected an expression body");
+		};
+		let ValuableKind::Expression(body) = &body.kind else {
+			panic!("Expected an expression body");
+		};
+		let TopLevelKind::Valuable(Valuable { kind: ValuableKind::Branch(branch), .. }) = &body[0].kind else {
+			panic!("Expected a branch");
+		};
+		assert!(matches!(branch.then[0].kind, TopLevelKind::Break));
+		assert!(matches!(branch.otherwise[0].kind, TopLevelKind::Continue));
+	}
+
+	/// Test that a for each keeps the names it binds.
+	#[test]
+	fn test_for_each() {
+		let input = "scores= name* score* [\n\tname$>scribe<\n]";
+
+		let parser = SwearParser::new();
+		let ast = parser.parse(input).expect("Failed to parse input");
+
+		let TopLevelKind::ForEach(each) = &ast[0].kind else {
+			panic!("Expected a for each");
+		};
+		assert_eq!(each.value.span.slice(input), "scores");
+		assert_eq!(each.names, vec!["name", "score"]);
+		assert_eq!(each.body.len(), 1);
+	}
+
+	/// Test that a blueprint keeps its constructor parameters.
+	#[test]
+	fn test_blueprint() {
+		let input = "Point: x* y* [\n\tsum! [x#>add y*<<]\n]\np% >Point '1'#* '2'#*<";
+
+		let parser = SwearParser::new();
+		let ast = parser.parse(input).expect("Failed to parse input");
+
+		let TopLevelKind::Definition(Definition::Blueprint { name, parameters, exprs, .. }) = &ast[0].kind else {
+			panic!("Expected a blueprint definition");
+		};
+		assert_eq!(name, "Point");
+		assert_eq!(parameters, &vec!["x", "y"]);
+		assert_eq!(exprs.len(), 1);
+
+		assert!(parser.parse("Empty: []").is_ok(), "Parameters are optional");
+	}
+
+	/// Test that parents can be listed alongside the parameters.
+	#[test]
+	fn test_blueprint_parents() {
+		let input = "Enemy: Entity: hp* Named: [\n\tdescribe! [super>describe<<]\n]";
+
+		let parser = SwearParser::new();
+		let ast = parser.parse(input).expect("Failed to parse input");
+
+		let TopLevelKind::Definition(Definition::Blueprint { parameters, parents, .. }) = &ast[0].kind else {
+			panic!("Expected a blueprint definition");
+		};
+		assert_eq!(parameters, &vec!["hp"]);
+		assert_eq!(parents, &vec!["Entity", "Named"]);
+	}
+
+	/// Test that a callback can be referenced without being called.
+	#[test]
+	fn test_reference() {
+		let input = "f% !double\n>apply !f* '2'#*<";
+
+		let parser = SwearParser::new();
+		let ast = parser.parse(input).expect("Failed to parse input");
+
+		let TopLevelKind::Definition(Definition::Register { value, .. }) = &ast[0].kind else {
+			panic!("Expected a register definition");
+		};
+		assert!(matches!(&value.kind, ValuableKind::Reference(name) if name == "double"));
+
+		let TopLevelKind::Valuable(Valuable { kind: ValuableKind::Callback(callback), .. }) = &ast[1].kind else {
+			panic!("Expected a callback");
+		};
+		assert!(matches!(&callback.parameters[0].kind, ValuableKind::Reference(name) if name == "f"));
+	}
+
 	/// Test parsing a basic script.
 	#[test]
 	fn test_parse() {
@@ -96,8 +338,10 @@ mod tests {
 		let ron = ron::ser::to_string(&ast).expect("Failed to serialize to RON");
 		println!("Serialized to RON:\n{}", ron);
 
-		ron::de::from_str::<Vec<TopLevelItem>>(&ron).expect("Failed to deserialize RON");
+		let de = ron::de::from_str::<Expression>(&ron).expect("Failed to deserialize RON");
 		println!("Successfully deserialized RON");
+
+		assert_eq!(spans(&ast), spans(&de), "Spans changed across the round trip");
 	}
 
 	/// Test parsing a basic script, then serializing and deserializing it using bitcode.
@@ -118,7 +362,9 @@ mod tests {
 		let bit = bitcode::serialize(&ast).expect("Failed to serialize to bitcode");
 		println!("Serialized to bitcode:\n{:?}", bit);
 
-		bitcode::deserialize::<Vec<TopLevelItem>>(&bit).expect("Failed to deserialize bitcode");
+		let de = bitcode::deserialize::<Expression>(&bit).expect("Failed to deserialize bitcode");
 		println!("Successfully deserialized bitcode");
+
+		assert_eq!(spans(&ast), spans(&de), "Spans changed across the round trip");
 	}
 }
@@ -0,0 +1,83 @@
+/// A range of bytes in the source a node was parsed from.
+#[cfg_attr(feature="serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct Span {
+	pub start: usize,
+	pub end: usize,
+}
+
+impl Span {
+	pub fn new(start: usize, end: usize) -> Self {
+		Self { start, end }
+	}
+
+	/// A zero length span at the given offset.
+	pub fn point(offset: usize) -> Self {
+		Self { start: offset, end: offset }
+	}
+
+	/// The smallest span covering both `self` and `other`.
+	pub fn join(self, other: Span) -> Self {
+		Self {
+			start: self.start.min(other.start),
+			end: self.end.max(other.end),
+		}
+	}
+
+	pub fn len(&self) -> usize {
+		self.end.saturating_sub(self.start)
+	}
+
+	pub fn is_empty(&self) -> bool {
+		self.len() == 0
+	}
+
+	/// The text this span covers, or an empty string if it doesn't fit `source`.
+	pub fn slice<'s>(&self, source: &'s str) -> &'s str {
+		source.get(self.start..self.end).unwrap_or_default()
+	}
+
+	/// The line and column this span starts at.
+	pub fn start_location(&self, source: &str) -> Location {
+		Location::from_offset(source, self.start)
+	}
+
+	/// The line and column this span ends at.
+	pub fn end_location(&self, source: &str) -> Location {
+		Location::from_offset(source, self.end)
+	}
+}
+
+/// A one-based line and column in some source text.
+/// The column counts characters, not bytes.
+#[cfg_attr(feature="serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Location {
+	pub line: usize,
+	pub column: usize,
+}
+
+impl Location {
+	/// Finds the location of a byte offset.
+	/// Offsets past the end of `source` are clamped to it.
+	pub fn from_offset(source: &str, offset: usize) -> Self {
+		let mut offset = offset.min(source.len());
+		while !source.is_char_boundary(offset) {
+			offset -= 1;
+		}
+
+		let before = &source[..offset];
+		let line_start = before.rfind('\n').map(|i| i + 1).unwrap_or(0);
+
+		Self {
+			line: before.matches('\n').count() + 1,
+			column: before[line_start..].chars().count() + 1,
+		}
+	}
+}
+
+impl std::fmt::Display for Location {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		write!(f, "{}:{}", self.line, self.column)
+	}
+}
@@ -0,0 +1,18 @@
+use super::*;
+
+/// Evaluates one of two expressions, depending on a condition.
+///
+/// Written as `cond? [then] [otherwise]`.
+#[cfg_attr(feature="serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Debug, Clone)]
+pub struct Branch {
+	pub cond: Valuable,
+	pub then: Expression,
+	pub otherwise: Expression,
+}
+
+impl Branch {
+	pub fn new(cond: Valuable, then: Expression, otherwise: Expression) -> Self {
+		Self { cond, then, otherwise }
+	}
+}
@@ -1,49 +1,84 @@
 mod expressions;
 mod objects;
 mod callbacks;
+mod branches;
+
+use std::ops::Deref;
 
 use super::*;
 
 pub use expressions::Expression;
 pub use callbacks::MethodCallback;
+pub use branches::Branch;
 pub use objects::*;
 
+/// Anything that produces an Object, along with where it came from.
 #[cfg_attr(feature="serde", derive(serde::Serialize, serde::Deserialize))]
 #[derive(Debug, Clone)]
-pub enum Valuable {
+pub struct Valuable {
+	pub kind: ValuableKind,
+	pub span: Span,
+}
+
+impl Valuable {
+	pub fn new(kind: impl Into<ValuableKind>, span: Span) -> Self {
+		Self { kind: kind.into(), span }
+	}
+}
+
+impl Deref for Valuable {
+	type Target = ValuableKind;
+
+	fn deref(&self) -> &Self::Target {
+		&self.kind
+	}
+}
+
+#[cfg_attr(feature="serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Debug, Clone)]
+pub enum ValuableKind {
 	ObjectLiteral(ObjectLiteral),
 	ObjectConversion(Box<ObjectConversion>),
 	Callback(MethodCallback),
+	Branch(Box<Branch>),
 	Expression(Expression),
 	Identifier(String),
+	/// `!name`, a callback as an Object.
+	Reference(String),
 }
 
-impl From<ObjectLiteral> for Valuable {
+impl From<ObjectLiteral> for ValuableKind {
 	fn from(value: ObjectLiteral) -> Self {
-		Valuable::ObjectLiteral(value)
+		ValuableKind::ObjectLiteral(value)
 	}
 }
 
-impl From<ObjectConversion> for Valuable {
+impl From<ObjectConversion> for ValuableKind {
 	fn from(value: ObjectConversion) -> Self {
-		Valuable::ObjectConversion(Box::new(value))
+		ValuableKind::ObjectConversion(Box::new(value))
+	}
+}
+
+impl From<Branch> for ValuableKind {
+	fn from(value: Branch) -> Self {
+		ValuableKind::Branch(Box::new(value))
 	}
 }
 
-impl From<MethodCallback> for Valuable {
+impl From<MethodCallback> for ValuableKind {
 	fn from(value: MethodCallback) -> Self {
-		Valuable::Callback(value)
+		ValuableKind::Callback(value)
 	}
 }
 
-impl From<Expression> for Valuable {
+impl From<Expression> for ValuableKind {
 	fn from(value: Expression) -> Self {
-		Valuable::Expression(value)
+		ValuableKind::Expression(value)
 	}
 }
 
-impl From<String> for Valuable {
+impl From<String> for ValuableKind {
 	fn from(value: String) -> Self {
-		Valuable::Identifier(value)
+		ValuableKind::Identifier(value)
 	}
 }
@@ -5,7 +5,8 @@ use super::*;
 pub enum ObjectLiteral {
 	Chars(String),
 	State(bool),
-	Count(f64),
+	//? Kept as text so the runtime can parse it at whatever precision it likes.
+	Count(String),
 	Zip,
 	Deck(Vec<ObjectLiteral>),
 	Map(Vec<(ObjectLiteral, ObjectLiteral)>),
@@ -21,7 +22,7 @@ impl ObjectLiteral {
 	}
 
 	pub fn new_count(value: &str) -> Self {
-		ObjectLiteral::Count(escape_text(value).parse().unwrap_or(1.)) //FIXME
+		ObjectLiteral::Count(escape_text(value))
 	}
 
 	pub fn new_zip(_value: &str) -> Self {